@@ -71,38 +71,94 @@
     clippy::zero_sized_map_values
 )]
 
-use infrastructure::rand::thread_rng;
-use infrastructure::rand::Rng;
+use self::config::Config;
+use self::config::Deadlines;
+use self::config::ProofOfWorkConfig;
+use self::difficulty_controller::DifficultyController;
+use self::metrics::Metrics;
+use self::protected_resource::watch;
+use self::protected_resource::ProtectedResource;
+use self::proxy::Proxy;
+use self::shutdown::load_challenges;
+use self::shutdown::store_challenges;
+use self::shutdown::wait_for_termination;
+use self::shutdown::Shutdown;
+use self::verification_batcher::VerificationBatcher;
+use infrastructure::serde::Serialize;
 use infrastructure::tokio::net::TcpListener;
 use infrastructure::tokio::net::TcpStream;
 use infrastructure::tokio::runtime::Builder;
+use infrastructure::tokio::select;
+use infrastructure::tokio::signal::unix::signal;
+use infrastructure::tokio::signal::unix::SignalKind;
 use infrastructure::tokio::spawn;
+use infrastructure::tokio::task::JoinSet;
+use infrastructure::tokio::time::timeout;
+use infrastructure::tracing::debug;
+use infrastructure::tracing::error;
+use infrastructure::tracing::field::display;
+use infrastructure::tracing::field::Empty;
+use infrastructure::tracing::info;
+use infrastructure::tracing::info_span;
+use infrastructure::tracing::warn;
+use infrastructure::tracing::Instrument;
+use infrastructure::tracing::Span;
 use infrastructure::uuid::Uuid;
+use infrastructure::AccessTokenIssuer;
+use infrastructure::AccessTokenStatus;
+use infrastructure::Admission;
+use infrastructure::Algorithm;
+use infrastructure::ChallengeTable;
+use infrastructure::IssuedChallenge;
+use infrastructure::LegacyRequest;
+use infrastructure::LegacyResponse;
+use infrastructure::LegacyWordOfWisdom;
+use infrastructure::LogDestination;
+use infrastructure::Logger;
+use infrastructure::Proof;
+use infrastructure::ProofPayload;
 use infrastructure::Protocol;
+use infrastructure::QuoteFilter;
 use infrastructure::Request;
 use infrastructure::Response;
-use infrastructure::Secret;
 use infrastructure::Serializer;
+use infrastructure::Timeout;
 use infrastructure::WordOfWisdom;
 use infrastructure::POW;
 use infrastructure::SERVER_SOCKET_ADDRESS;
-use infrastructure::WORD_OF_WISDOM_QUOTES;
-use std::borrow::Cow;
-use std::collections::HashMap;
 use std::convert::From;
 use std::error::Error;
+use std::fs::read_to_string;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::pin::pin;
+use std::process::exit;
 use std::sync::Arc;
-use std::sync::Mutex;
 
 fn main() -> () {
-    if let Err(error) = process() {
-        println!("{}", &error);
+    // The log is not written, until the logger is initialized.
+    let logger = match Logger::initialize("info", LogDestination::Stdout) {
+        Ok(logger_) => logger_,
+        Err(error) => {
+            println!("{}", &error);
+
+            exit(1);
+        }
+    };
+
+    if let Err(error) = process(logger) {
+        error!("{}", &error);
+
+        exit(1);
     }
 
     return ();
 }
 
-fn process() -> Result<(), Box<dyn Error + 'static>> {
+fn process(logger: Logger) -> Result<(), Box<dyn Error + 'static>> {
+    let config = Config::from_environment()?;
+
     let runtime = match Builder::new_multi_thread().enable_all().build() {
         Ok(runtime_) => runtime_,
         Err(error) => {
@@ -110,158 +166,3813 @@ fn process() -> Result<(), Box<dyn Error + 'static>> {
         }
     };
 
-    if let Err(error) = runtime.block_on(run_tcp_server()) {
-        return Err(Box::from(error));
+    let shutdown = runtime.block_on(async move {
+        let tcp_listener = match TcpListener::bind(SERVER_SOCKET_ADDRESS).await {
+            Ok(tcp_listener_) => tcp_listener_,
+            Err(error) => {
+                return Err(Box::from(error));
+            }
+        };
+
+        if let Some(log_filter_file_path) = config.log_filter_file_path.clone() {
+            spawn(reload_log_filter(logger, log_filter_file_path));
+        }
+
+        return run_tcp_server(config, tcp_listener, wait_for_termination()).await;
+    })?;
+
+    info!("{}", &shutdown);
+
+    // The connections, that are not drained, are aborted, so, it is the failure.
+    if !shutdown.is_complete() {
+        return Err(Box::from(
+            "The connections are not drained within the SHUTDOWN_DRAIN_TIMEOUT_SECONDS.",
+        ));
     }
 
     return Ok(());
 }
 
-type RequestsState = Arc<Mutex<HashMap<Uuid, Secret>>>;
-
-async fn run_tcp_server() -> Result<(), Box<dyn Error + 'static>> {
-    let requests_state: RequestsState = Arc::new(Mutex::new(HashMap::new()));
-
-    let tcp_listener = match TcpListener::bind(SERVER_SOCKET_ADDRESS).await {
-        Ok(tcp_listener_) => tcp_listener_,
+// The environment of the running process is not changed, so, the filter is read from the file on the SIGHUP.
+// The filter, that is not read or is invalid, does not replace the current one.
+async fn reload_log_filter(logger: Logger, log_filter_file_path: PathBuf) -> () {
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup_) => hangup_,
         Err(error) => {
-            return Err(Box::from(error));
+            error!("{}", &error);
+
+            return ();
         }
     };
 
-    loop {
-        let tcp_stream = match tcp_listener.accept().await {
-            Ok((tcp_stream_, _)) => tcp_stream_,
+    while hangup.recv().await.is_some() {
+        let filter = match read_to_string(log_filter_file_path.as_path()) {
+            Ok(filter_) => filter_,
             Err(error) => {
-                return Err(Box::from(error));
+                warn!("{} The log filter is kept.", &error);
+
+                continue;
             }
         };
 
-        spawn(handle_stream(tcp_stream, requests_state.clone()));
+        match logger.reload(filter.trim()) {
+            Ok(()) => {
+                info!("The log filter is reloaded: {}.", filter.trim());
+            }
+            Err(error) => {
+                warn!("{} The log filter is kept.", &error);
+            }
+        }
     }
 
-    return Ok(());
+    return ();
 }
 
-async fn handle_stream(mut tcp_stream: TcpStream, requests_state: RequestsState) -> () {
-    let data = match Protocol::receive(&mut tcp_stream).await {
-        Ok(data_) => data_,
-        Err(error) => {
-            println!("{}", &error);
+#[derive(Clone)]
+struct Environment {
+    challenge_table: Arc<ChallengeTable>,
+    proof_of_work: Arc<ProofOfWorkConfig>,
+    difficulty_controller: Arc<DifficultyController>,
+    verification_batcher: Arc<VerificationBatcher>,
+    protected_resource: Arc<dyn ProtectedResource>,
+    // Is absent, if the proxy mode is not configured.
+    proxy: Option<Arc<Proxy>>,
+    // Is absent, if the access tokens are not issued.
+    access_token_issuer: Option<Arc<AccessTokenIssuer>>,
+    deadlines: Deadlines,
+    metrics: Arc<Metrics>,
+}
+
+// Serves the connections, until the shutdown is completed, then stops accepting and drains the connections.
+// The listener and the shutdown are given, so, the server is started and stopped in-process as well.
+async fn run_tcp_server<F>(
+    config: Config,
+    tcp_listener: TcpListener,
+    shutdown: F,
+) -> Result<Shutdown, Box<dyn Error + 'static>>
+where
+    F: Future<Output = ()>,
+{
+    let challenge_table = Arc::new(ChallengeTable::new(
+        ChallengeTable::DEFAULT_SHARDS_QUANTITY,
+        config.challenge_lifetime,
+        config.maximum_client_challenges_quantity,
+    ));
+
+    if let Some(ref challenges_file_path) = config.challenges_file_path {
+        let challenges_quantity =
+            load_challenges(challenges_file_path.as_path(), &challenge_table)?;
+
+        info!("{} challenges are loaded.", challenges_quantity);
+    }
+
+    let environment = Environment {
+        challenge_table,
+        proof_of_work: Arc::new(config.proof_of_work),
+        difficulty_controller: Arc::new(DifficultyController::new(config.difficulty_controller)),
+        verification_batcher: Arc::new(VerificationBatcher::new(config.verification_batcher)?),
+        protected_resource: config.protected_resource,
+        proxy: config
+            .proxy
+            .map(|proxy_config| Arc::new(Proxy::new(proxy_config))),
+        access_token_issuer: config.access_token_issuer.map(Arc::new),
+        deadlines: config.deadlines,
+        metrics: Arc::new(Metrics::new()),
+    };
+
+    let watching = spawn(watch(
+        environment.protected_resource.clone(),
+        config.protected_resource_refresh_interval,
+    ));
+
+    let reporting = spawn(Metrics::report(
+        environment.metrics.clone(),
+        config.metrics_report_interval,
+    ));
+
+    let mut handlers: JoinSet<()> = JoinSet::new();
+
+    let mut shutdown_ = pin!(shutdown);
+
+    'a: loop {
+        select! {
+            _ = &mut shutdown_ => {
+                break 'a;
+            }
+            accepted = tcp_listener.accept() => {
+                let (tcp_stream, socket_address) = match accepted {
+                    Ok(accepted_) => accepted_,
+                    Err(error) => {
+                        return Err(Box::from(error));
+                    }
+                };
+
+                handlers.spawn(
+                    handle_stream(tcp_stream, socket_address, environment.clone())
+                        .instrument(info_span!("connection", peer = %socket_address)),
+                );
+            }
+            // The finished handlers are forgotten, so, only the connections in progress are kept.
+            Some(joined) = handlers.join_next(), if !handlers.is_empty() => {
+                if let Err(error) = joined {
+                    error!("{}", &error);
+                }
+            }
+        }
+    }
+
+    // No more connections are accepted.
+    drop(tcp_listener);
+
+    let connections_quantity = handlers.len();
+
+    info!(
+        "The shutdown is requested, {} connections are in progress.",
+        connections_quantity
+    );
+
+    let is_drained = timeout(config.drain_timeout, async {
+        while let Some(joined) = handlers.join_next().await {
+            if let Err(error) = joined {
+                error!("{}", &error);
+            }
+        }
+    })
+    .await
+    .is_ok();
+
+    let aborted_connections_quantity = handlers.len();
+
+    handlers.shutdown().await;
+
+    watching.abort();
+
+    reporting.abort();
+
+    // The challenges are stored after the handlers, that return them back, are finished.
+    let stored_challenges_quantity = match config.challenges_file_path {
+        Some(ref challenges_file_path) => Some(store_challenges(
+            challenges_file_path.as_path(),
+            &environment.challenge_table,
+        )?),
+        None => None,
+    };
+
+    return Ok(Shutdown {
+        connections_quantity,
+        aborted_connections_quantity: if is_drained {
+            0
+        } else {
+            aborted_connections_quantity
+        },
+        stored_challenges_quantity,
+    });
+}
+
+// The request is handled within the request timeout, but the spliced connection is not limited.
+async fn handle_stream(
+    mut tcp_stream: TcpStream,
+    socket_address: SocketAddr,
+    environment: Environment,
+) -> () {
+    // The fields are recorded, as they become known, and the outcome is recorded, when the request is handled.
+    let span = info_span!(
+        "request",
+        kind = Empty,
+        token = Empty,
+        difficulty = Empty,
+        outcome = Empty
+    );
+
+    let proxy = match timeout(
+        environment.deadlines.request,
+        handle_request(&mut tcp_stream, socket_address, &environment).instrument(span.clone()),
+    )
+    .await
+    {
+        Ok(Ok(proxy_)) => {
+            span.in_scope(|| {
+                info!("The request is handled.");
+            });
+
+            proxy_
+        }
+        Ok(Err(error)) => {
+            span.record(
+                "outcome",
+                if error.is::<Timeout>() {
+                    "timeout"
+                } else {
+                    "error"
+                },
+            );
+
+            span.in_scope(|| {
+                warn!("{}", &error);
+            });
+
+            return ();
+        }
+        Err(_) => {
+            environment.metrics.record_timeout(Timeout::Request);
+
+            span.record("outcome", "timeout");
+
+            span.in_scope(|| {
+                warn!("{}", &Timeout::Request);
+            });
 
             return ();
         }
     };
 
-    let request = match Serializer::deserialize::<'_, Request>(data.as_slice()) {
-        Ok(request_) => request_,
+    if let Some(proxy_) = proxy {
+        if let Err(error) = proxy_.splice(&mut tcp_stream).await {
+            warn!("{}", &error);
+        }
+    }
+
+    return ();
+}
+
+// Is the proxy, if the admitted connection is spliced to its upstream.
+async fn handle_request<'a>(
+    tcp_stream: &'a mut TcpStream,
+    socket_address: SocketAddr,
+    environment: &'a Environment,
+) -> Result<Option<Arc<Proxy>>, Box<dyn Error + 'static>> {
+    let data = match Protocol::receive_within(
+        tcp_stream,
+        Some(environment.deadlines.header_read),
+        Some(environment.deadlines.body_read),
+    )
+    .await
+    {
+        Ok(data_) => data_,
         Err(error) => {
-            println!("{}", &error);
+            record_timeout(environment, error.as_ref());
 
-            return ();
+            return Err(error);
         }
     };
 
+    let incoming_request = read_request(data.as_slice())?;
+
+    let request = match incoming_request {
+        IncomingRequest::Current(request_) => request_,
+        IncomingRequest::Legacy { token, result } => {
+            environment
+                .difficulty_controller
+                .observe(socket_address.ip());
+
+            handle_legacy_word_of_wisdom(tcp_stream, socket_address, environment, token, result)
+                .await?;
+
+            return Ok(None);
+        }
+    };
+
+    environment
+        .difficulty_controller
+        .observe(socket_address.ip());
+
+    let span = Span::current();
+
     match request {
-        Request::Challenge { token } => {
-            let secret = Secret {
-                value: Uuid::new_v4(),
+        Request::Challenge { token, algorithms } => {
+            if algorithms.is_empty() {
+                handle_legacy_challenge(
+                    tcp_stream,
+                    socket_address,
+                    environment,
+                    token.into_owned(),
+                )
+                .await?;
+
+                return Ok(None);
+            }
+
+            span.record("kind", "challenge");
+
+            span.record("token", display(&token));
+
+            let response = match negotiate_algorithm(
+                environment.proof_of_work.algorithms.as_slice(),
+                algorithms.as_slice(),
+            ) {
+                Some(algorithm) => {
+                    let p_o_w = POW::new_with_parameters(
+                        algorithm,
+                        environment.proof_of_work.parameters(algorithm),
+                    )?;
+
+                    let decision = environment
+                        .difficulty_controller
+                        .decide(socket_address.ip(), environment.challenge_table.len());
+
+                    info!("{}", &decision);
+
+                    environment.metrics.record_difficulty_decision(&decision);
+
+                    let challenge = p_o_w.generate_multi_puzzle_challenge(
+                        environment
+                            .proof_of_work
+                            .difficulty(algorithm, decision.difficulty),
+                        environment.proof_of_work.puzzles_quantity(algorithm),
+                    )?;
+
+                    span.record("difficulty", challenge.difficulty.zero_bits());
+
+                    span.record("outcome", "issued");
+
+                    if !environment.challenge_table.insert_for(
+                        socket_address.ip(),
+                        token.into_owned(),
+                        challenge.clone(),
+                    ) {
+                        return Err(Box::from(
+                            "The client has the maximum quantity of the outstanding challenges.",
+                        ));
+                    }
+
+                    Response::Challenge { challenge }
+                }
+                None => {
+                    span.record("outcome", "algorithm_mismatch");
+
+                    Response::AlgorithmMismatch {
+                        algorithms: environment.proof_of_work.algorithms.clone(),
+                    }
+                }
             };
 
-            {
-                let mut mutex_guard = match requests_state.lock() {
-                    Ok(mutex_guard_) => mutex_guard_,
+            respond(tcp_stream, environment, &response).await?;
+        }
+        Request::WordOfWisdom {
+            token,
+            proof,
+            filter,
+        } => {
+            span.record("kind", "word_of_wisdom");
+
+            let token_ = token.into_owned();
+
+            let verified_challenge = redeem(environment, token_, proof).await?;
+
+            let word_of_wisdom = match verified_challenge {
+                Some(issued_challenge) => match environment
+                    .protected_resource
+                    .provide(socket_address.ip(), &filter)
+                    .await
+                {
+                    Ok(Some(payload)) => WordOfWisdom::Result {
+                        result: payload,
+                        access_token: environment
+                            .access_token_issuer
+                            .as_ref()
+                            .map(|access_token_issuer| access_token_issuer.issue()),
+                    },
+                    Ok(None) => {
+                        environment
+                            .challenge_table
+                            .restore(token_, issued_challenge);
+
+                        WordOfWisdom::NoMatch
+                    }
                     Err(error) => {
-                        println!("{}", &error);
+                        environment
+                            .challenge_table
+                            .restore(token_, issued_challenge);
 
-                        return ();
+                        return Err(error);
                     }
-                };
+                },
+                None => WordOfWisdom::Fail,
+            };
 
-                mutex_guard.insert(token.into_owned(), secret.clone());
-            }
+            span.record("outcome", outcome_of(&word_of_wisdom));
 
-            let response = Response::Challenge { secret };
+            let response = Response::WordOfWisdom { word_of_wisdom };
 
-            let data = match Serializer::serialize(&response) {
-                Ok(data_) => data_,
-                Err(error) => {
-                    println!("{}", &error);
+            respond(tcp_stream, environment, &response).await?;
+        }
+        Request::Admission {
+            token,
+            proof,
+            keep_connection,
+        } => {
+            span.record("kind", "admission");
 
-                    return ();
+            let proxy = match environment.proxy {
+                Some(ref proxy_) => proxy_.clone(),
+                None => {
+                    return Err(Box::from(
+                        "The admission is requested, but the proxy is not configured.",
+                    ));
                 }
             };
 
-            if let Err(error) = Protocol::send(&mut tcp_stream, data).await {
-                println!("{}", &error);
+            let admission = match redeem(environment, token.into_owned(), proof).await? {
+                Some(_) => {
+                    if keep_connection {
+                        Admission::Admitted
+                    } else {
+                        Admission::Ticket {
+                            ticket: proxy.issue_ticket(),
+                            lifetime_seconds: proxy.ticket_lifetime().as_secs(),
+                        }
+                    }
+                }
+                None => Admission::Fail,
+            };
 
-                return ();
+            proxy.record_admission(&admission);
+
+            let is_admitted = matches!(admission, Admission::Admitted);
+
+            respond(tcp_stream, environment, &Response::Admission { admission }).await?;
+
+            if is_admitted {
+                return Ok(Some(proxy));
             }
         }
-        Request::WordOfWisdom { token, result } => {
-            let mut p_o_w = POW::new(POW::DEFAULT_DIFFICULTY);
+        Request::Enter { ticket } => {
+            span.record("kind", "enter");
 
-            let all_right = {
-                let mut mutex_guard = match requests_state.lock() {
-                    Ok(mutex_guard_) => mutex_guard_,
-                    Err(error) => {
-                        println!("{}", &error);
+            span.record("token", display(&ticket));
 
-                        return ();
-                    }
-                };
+            let proxy = match environment.proxy {
+                Some(ref proxy_) => proxy_.clone(),
+                None => {
+                    return Err(Box::from(
+                        "The entrance is requested, but the proxy is not configured.",
+                    ));
+                }
+            };
 
-                match mutex_guard.get(token.as_ref()) {
-                    Some(secret) => {
-                        let result = match p_o_w.verify_nonce(secret, result) {
-                            Ok(result_) => result_,
-                            Err(error) => {
-                                println!("{}", &error);
+            let admission = if proxy.redeem_ticket(&ticket) {
+                Admission::Admitted
+            } else {
+                Admission::Fail
+            };
 
-                                return ();
-                            }
-                        };
+            proxy.record_admission(&admission);
 
-                        if result {
-                            mutex_guard.remove(token.as_ref());
-                        }
+            let is_admitted = matches!(admission, Admission::Admitted);
 
-                        result
-                    }
-                    None => false,
+            respond(tcp_stream, environment, &Response::Admission { admission }).await?;
+
+            if is_admitted {
+                return Ok(Some(proxy));
+            }
+        }
+        Request::Admit { token, proof } => {
+            span.record("kind", "admit");
+
+            let access_token_issuer = match environment.access_token_issuer {
+                Some(ref access_token_issuer_) => access_token_issuer_.clone(),
+                None => {
+                    return Err(Box::from(
+                        "The access token is requested, but the access tokens are not issued.",
+                    ));
                 }
             };
 
-            let word_of_wisdom = if all_right {
-                let word_of_wisdom = WORD_OF_WISDOM_QUOTES[
-                    thread_rng().gen_range::<usize, _>(0..WORD_OF_WISDOM_QUOTES.len())
-                ];
+            let access_token = redeem(environment, token.into_owned(), proof)
+                .await?
+                .map(|_| access_token_issuer.issue());
+
+            span.record(
+                "outcome",
+                if access_token.is_some() {
+                    "issued"
+                } else {
+                    "failed"
+                },
+            );
 
-                WordOfWisdom::Result {
-                    result: Cow::Borrowed(word_of_wisdom),
+            respond(tcp_stream, environment, &Response::Admit { access_token }).await?;
+        }
+        Request::Access {
+            access_token,
+            filter,
+        } => {
+            span.record("kind", "access");
+
+            span.record("token", display(&access_token.id));
+
+            let access_token_issuer = match environment.access_token_issuer {
+                Some(ref access_token_issuer_) => access_token_issuer_.clone(),
+                None => {
+                    return Err(Box::from(
+                        "The access is requested, but the access tokens are not issued.",
+                    ));
                 }
-            } else {
-                WordOfWisdom::Fail
             };
 
-            let response = Response::WordOfWisdom { word_of_wisdom };
+            // The request is spent before the resource is provided, so, the concurrent requests
+            // do not exceed the quantity, and it is returned back, if the resource is not provided.
+            let word_of_wisdom = match access_token_issuer.spend(&access_token) {
+                AccessTokenStatus::Valid => match environment
+                    .protected_resource
+                    .provide(socket_address.ip(), &filter)
+                    .await
+                {
+                    Ok(Some(payload)) => WordOfWisdom::Result {
+                        result: payload,
+                        access_token: None,
+                    },
+                    Ok(None) => {
+                        access_token_issuer.refund(&access_token);
 
-            let data = match Serializer::serialize(&response) {
-                Ok(data_) => data_,
-                Err(error) => {
-                    println!("{}", &error);
+                        WordOfWisdom::NoMatch
+                    }
+                    Err(error) => {
+                        access_token_issuer.refund(&access_token);
 
-                    return ();
+                        return Err(error);
+                    }
+                },
+                status => {
+                    debug!("The access token is denied: {:?}.", status);
+
+                    WordOfWisdom::AccessDenied
                 }
             };
 
-            if let Err(error) = Protocol::send(&mut tcp_stream, data).await {
-                println!("{}", &error);
+            span.record("outcome", outcome_of(&word_of_wisdom));
 
-                return ();
+            respond(
+                tcp_stream,
+                environment,
+                &Response::WordOfWisdom { word_of_wisdom },
+            )
+            .await?;
+        }
+    }
+
+    return Ok(None);
+}
+
+enum IncomingRequest<'a> {
+    Current(Request<'a>),
+    Legacy { token: Uuid, result: u64 },
+}
+
+// The WordOfWisdom request of the clients, that do not negotiate, has the single u64 result instead of the proof.
+// Their Challenge request is read as the current one without the algorithms.
+fn read_request<'a>(data: &'a [u8]) -> Result<IncomingRequest<'a>, Box<dyn Error + 'static>> {
+    let error = match Serializer::deserialize::<'_, Request>(data) {
+        Ok(request) => {
+            return Ok(IncomingRequest::Current(request));
+        }
+        Err(error_) => error_,
+    };
+
+    return match Serializer::deserialize::<'_, LegacyRequest>(data) {
+        Ok(LegacyRequest::WordOfWisdom { token, result }) => Ok(IncomingRequest::Legacy {
+            token: token.into_owned(),
+            result,
+        }),
+        _ => Err(error),
+    };
+}
+
+// The clients, that do not negotiate, solve only the double SHA-256 nonce of the default difficulty,
+// so, no difficulty is decided for them, but their outstanding challenges count for the load.
+async fn handle_legacy_challenge<'a>(
+    tcp_stream: &'a mut TcpStream,
+    socket_address: SocketAddr,
+    environment: &'a Environment,
+    token: Uuid,
+) -> Result<(), Box<dyn Error + 'static>> {
+    let span = Span::current();
+
+    span.record("kind", "legacy_challenge");
+
+    span.record("token", display(&token));
+
+    if negotiate_algorithm(environment.proof_of_work.algorithms.as_slice(), &[]).is_none() {
+        span.record("outcome", "algorithm_mismatch");
+
+        return Err(Box::from(
+            "The client does not negotiate, but the default algorithm is not served.",
+        ));
+    }
+
+    let challenge = POW::new(POW::DEFAULT_ALGORITHM)?.generate_challenge(POW::DEFAULT_DIFFICULTY);
+
+    span.record("difficulty", challenge.difficulty.zero_bits());
+
+    span.record("outcome", "issued");
+
+    let secret = challenge.secret.clone();
+
+    if !environment
+        .challenge_table
+        .insert_for(socket_address.ip(), token, challenge)
+    {
+        return Err(Box::from(
+            "The client has the maximum quantity of the outstanding challenges.",
+        ));
+    }
+
+    return respond(
+        tcp_stream,
+        environment,
+        &LegacyResponse::Challenge { secret },
+    )
+    .await;
+}
+
+async fn handle_legacy_word_of_wisdom<'a>(
+    tcp_stream: &'a mut TcpStream,
+    socket_address: SocketAddr,
+    environment: &'a Environment,
+    token: Uuid,
+    result: u64,
+) -> Result<(), Box<dyn Error + 'static>> {
+    let span = Span::current();
+
+    span.record("kind", "legacy_word_of_wisdom");
+
+    let proof = ProofPayload::encode(
+        POW::DEFAULT_ALGORITHM,
+        &Proof::Nonce(result.to_be_bytes().to_vec()),
+    )?;
+
+    let verified_challenge = redeem(environment, token, proof).await?;
+
+    let word_of_wisdom = match verified_challenge {
+        Some(issued_challenge) => match environment
+            .protected_resource
+            .provide(socket_address.ip(), &QuoteFilter::default())
+            .await
+        {
+            Ok(Some(payload)) => LegacyWordOfWisdom::Result {
+                result: payload.into_text(),
+            },
+            Ok(None) => {
+                environment.challenge_table.restore(token, issued_challenge);
+
+                LegacyWordOfWisdom::Fail
             }
+            Err(error) => {
+                environment.challenge_table.restore(token, issued_challenge);
+
+                return Err(error);
+            }
+        },
+        None => LegacyWordOfWisdom::Fail,
+    };
+
+    span.record(
+        "outcome",
+        match word_of_wisdom {
+            LegacyWordOfWisdom::Result { .. } => "served",
+            LegacyWordOfWisdom::Fail => "failed",
+        },
+    );
+
+    return respond(
+        tcp_stream,
+        environment,
+        &LegacyResponse::WordOfWisdom { word_of_wisdom },
+    )
+    .await;
+}
+
+fn outcome_of<'a>(word_of_wisdom: &'a WordOfWisdom<'a>) -> &'static str {
+    return match *word_of_wisdom {
+        WordOfWisdom::Result { .. } => "served",
+        WordOfWisdom::Fail => "failed",
+        WordOfWisdom::NoMatch => "no_match",
+        WordOfWisdom::AccessDenied => "access_denied",
+    };
+}
+
+async fn respond<'a, T>(
+    tcp_stream: &'a mut TcpStream,
+    environment: &'a Environment,
+    response: &'a T,
+) -> Result<(), Box<dyn Error + 'static>>
+where
+    T: Serialize,
+{
+    let data = match Serializer::serialize(response) {
+        Ok(data_) => data_,
+        Err(error) => {
+            return Err(error);
         }
+    };
+
+    if let Err(error) =
+        Protocol::send_within(tcp_stream, data, Some(environment.deadlines.response_write)).await
+    {
+        record_timeout(environment, error.as_ref());
+
+        return Err(error);
+    }
+
+    return Ok(());
+}
+
+fn record_timeout<'a>(environment: &'a Environment, error: &'a (dyn Error + 'static)) -> () {
+    if let Some(timeout_) = error.downcast_ref::<Timeout>() {
+        environment.metrics.record_timeout(*timeout_);
     }
 
     return ();
 }
+
+// The challenge is taken, so, the same challenge is never verified twice at the same time.
+// The challenge is spent by the failed check too, so, every verification costs the client the new work.
+async fn redeem<'a>(
+    environment: &'a Environment,
+    token: Uuid,
+    proof: ProofPayload,
+) -> Result<Option<IssuedChallenge>, Box<dyn Error + 'static>> {
+    let span = Span::current();
+
+    span.record("token", display(&token));
+
+    let verified_challenge = match environment.challenge_table.take(&token) {
+        Some(issued_challenge) => {
+            span.record(
+                "difficulty",
+                issued_challenge.challenge.difficulty.zero_bits(),
+            );
+
+            match environment
+                .verification_batcher
+                .verify(issued_challenge.challenge.clone(), proof)
+                .await
+            {
+                Ok(true) => Some(issued_challenge),
+                Ok(false) => None,
+                Err(error) => {
+                    return Err(error);
+                }
+            }
+        }
+        None => None,
+    };
+
+    environment
+        .metrics
+        .record_redemption(verified_challenge.is_some());
+
+    return Ok(verified_challenge);
+}
+
+// The first algorithm of the server's preference, that is supported by the client.
+// The clients, that do not negotiate, support only the default algorithm.
+fn negotiate_algorithm<'a>(
+    server_algorithms: &'a [Algorithm],
+    client_algorithms: &'a [Algorithm],
+) -> Option<Algorithm> {
+    if client_algorithms.is_empty() {
+        return server_algorithms
+            .iter()
+            .copied()
+            .find(|algorithm| *algorithm == POW::DEFAULT_ALGORITHM);
+    }
+
+    return server_algorithms
+        .iter()
+        .copied()
+        .find(|algorithm| client_algorithms.contains(algorithm));
+}
+
+mod config {
+    use super::difficulty_controller::DifficultyControllerConfig;
+    use super::protected_resource::ProtectedResource;
+    use super::protected_resource::QuoteFile;
+    use super::protected_resource::QuoteList;
+    use super::protected_resource::SelectionStrategy;
+    use super::protected_resource::UpstreamForwarder;
+    use super::proxy::ProxyConfig;
+    use super::verification_batcher::VerificationBatcherConfig;
+    use infrastructure::AccessTokenIssuer;
+    use infrastructure::Algorithm;
+    use infrastructure::Challenge;
+    use infrastructure::ChallengeTable;
+    use infrastructure::Difficulty;
+    use infrastructure::GeneralizedBirthdayParameters;
+    use infrastructure::MemoryHardParameters;
+    use infrastructure::Parameters;
+    use infrastructure::SequentialSquaringParameters;
+    use infrastructure::POW;
+    use std::env::var;
+    use std::env::var_os;
+    use std::env::VarError;
+    use std::error::Error;
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use std::thread::available_parallelism;
+    use std::time::Duration;
+
+    pub struct Config {
+        pub proof_of_work: ProofOfWorkConfig,
+        pub difficulty_controller: DifficultyControllerConfig,
+        pub verification_batcher: VerificationBatcherConfig,
+        pub protected_resource: Arc<dyn ProtectedResource>,
+        pub protected_resource_refresh_interval: Duration,
+        pub proxy: Option<ProxyConfig>,
+        pub access_token_issuer: Option<AccessTokenIssuer>,
+        // The time, during which the connections in progress are finished after the shutdown is requested.
+        pub drain_timeout: Duration,
+        // The challenges are loaded from the file on start and are stored to it on shutdown, if it is given.
+        pub challenges_file_path: Option<PathBuf>,
+        // The challenge, that is not redeemed within it, expires.
+        pub challenge_lifetime: Duration,
+        pub maximum_client_challenges_quantity: usize,
+        pub deadlines: Deadlines,
+        pub metrics_report_interval: Duration,
+        // The log filter is read from the file on the SIGHUP, if it is given.
+        pub log_filter_file_path: Option<PathBuf>,
+    }
+
+    impl Config {
+        pub fn from_environment() -> Result<Self, Box<dyn Error + 'static>> {
+            let minimum = Self::read_difficulty("DIFFICULTY_MINIMUM", Difficulty::I)?;
+
+            let maximum = Self::read_difficulty("DIFFICULTY_MAXIMUM", Difficulty::IV)?;
+
+            let baseline = Self::read_difficulty("DIFFICULTY_BASELINE", POW::DEFAULT_DIFFICULTY)?;
+
+            if minimum > baseline || baseline > maximum {
+                return Err(Box::from(
+                    "The DIFFICULTY_MINIMUM <= DIFFICULTY_BASELINE <= DIFFICULTY_MAXIMUM condition is violated.",
+                ));
+            }
+
+            let difficulty_controller = DifficultyControllerConfig {
+                minimum,
+                maximum,
+                baseline,
+                step: Self::read("DIFFICULTY_STEP", 4)?,
+                window: Duration::from_secs(Self::read("DIFFICULTY_WINDOW_SECONDS", 10)?),
+                requests_threshold: Self::read("DIFFICULTY_REQUESTS_THRESHOLD", 100)?,
+                outstanding_challenges_threshold: Self::read(
+                    "DIFFICULTY_OUTSTANDING_CHALLENGES_THRESHOLD",
+                    50,
+                )?,
+                client_requests_threshold: Self::read("DIFFICULTY_CLIENT_REQUESTS_THRESHOLD", 10)?,
+            };
+
+            let memory_hard_parameters = MemoryHardParameters {
+                memory_cost: Self::read(
+                    "ARGON2ID_MEMORY_COST_KIB",
+                    MemoryHardParameters::DEFAULT.memory_cost,
+                )?,
+                time_cost: Self::read(
+                    "ARGON2ID_TIME_COST",
+                    MemoryHardParameters::DEFAULT.time_cost,
+                )?,
+                parallelism: Self::read(
+                    "ARGON2ID_PARALLELISM",
+                    MemoryHardParameters::DEFAULT.parallelism,
+                )?,
+            };
+
+            if memory_hard_parameters.validate().is_err() {
+                return Err(Box::from(
+                    "Invalid value of the ARGON2ID_MEMORY_COST_KIB, ARGON2ID_TIME_COST or ARGON2ID_PARALLELISM environment variable.",
+                ));
+            }
+
+            let generalized_birthday_parameters = GeneralizedBirthdayParameters {
+                bits_quantity: Self::read(
+                    "EQUIHASH_N",
+                    GeneralizedBirthdayParameters::DEFAULT.bits_quantity,
+                )?,
+                rounds_quantity: Self::read(
+                    "EQUIHASH_K",
+                    GeneralizedBirthdayParameters::DEFAULT.rounds_quantity,
+                )?,
+            };
+
+            if generalized_birthday_parameters.validate().is_err() {
+                return Err(Box::from(
+                    "Invalid value of the EQUIHASH_N or EQUIHASH_K environment variable.",
+                ));
+            }
+
+            let algorithms = Self::read_algorithms("POW_ALGORITHMS", POW::DEFAULT_ALGORITHM)?;
+
+            let squarings_quantity = Self::read(
+                "TIME_LOCK_SQUARINGS",
+                SequentialSquaringParameters::DEFAULT_SQUARINGS_QUANTITY,
+            )?;
+
+            // The modulus is generated once per the server start, so, the challenges are created cheaply.
+            let sequential_squaring_parameters = if algorithms.contains(&Algorithm::TimeLock) {
+                match SequentialSquaringParameters::generate(
+                    Self::read(
+                        "TIME_LOCK_MODULUS_BITS",
+                        SequentialSquaringParameters::DEFAULT_MODULUS_BITS_QUANTITY,
+                    )?,
+                    squarings_quantity,
+                ) {
+                    Ok(sequential_squaring_parameters_) => sequential_squaring_parameters_,
+                    Err(_) => {
+                        return Err(Box::from(
+                            "Invalid value of the TIME_LOCK_MODULUS_BITS or TIME_LOCK_SQUARINGS environment variable.",
+                        ));
+                    }
+                }
+            } else {
+                SequentialSquaringParameters::unkeyed(squarings_quantity)
+            };
+
+            let puzzles_quantity = Self::read("POW_PUZZLES_QUANTITY", 1)?;
+
+            if puzzles_quantity == 0 || puzzles_quantity > Challenge::MAXIMUM_PUZZLES_QUANTITY {
+                return Err(Self::invalid_value("POW_PUZZLES_QUANTITY"));
+            }
+
+            let verification_batcher = VerificationBatcherConfig {
+                batch_size: Self::read("VERIFICATION_BATCH_SIZE", 32)?,
+                threads_quantity: Self::read(
+                    "VERIFICATION_THREADS_QUANTITY",
+                    available_parallelism().map_or(1, NonZeroUsize::get),
+                )?,
+            };
+
+            if verification_batcher.batch_size == 0 {
+                return Err(Self::invalid_value("VERIFICATION_BATCH_SIZE"));
+            }
+
+            if verification_batcher.threads_quantity == 0 {
+                return Err(Self::invalid_value("VERIFICATION_THREADS_QUANTITY"));
+            }
+
+            let proof_of_work = ProofOfWorkConfig {
+                algorithms,
+                puzzles_quantity,
+                memory_hard_parameters,
+                memory_hard_difficulty_relaxation: Self::read(
+                    "ARGON2ID_DIFFICULTY_RELAXATION",
+                    12,
+                )?,
+                generalized_birthday_parameters,
+                generalized_birthday_difficulty_relaxation: Self::read(
+                    "EQUIHASH_DIFFICULTY_RELAXATION",
+                    20,
+                )?,
+                sequential_squaring_parameters,
+            };
+
+            let maximum_client_challenges_quantity = Self::read(
+                "CHALLENGE_CLIENT_MAXIMUM_QUANTITY",
+                ChallengeTable::DEFAULT_MAXIMUM_CLIENT_CHALLENGES_QUANTITY,
+            )?;
+
+            if maximum_client_challenges_quantity == 0 {
+                return Err(Self::invalid_value("CHALLENGE_CLIENT_MAXIMUM_QUANTITY"));
+            }
+
+            return Ok(Self {
+                proof_of_work,
+                difficulty_controller,
+                verification_batcher,
+                protected_resource: Self::read_protected_resource()?,
+                protected_resource_refresh_interval: Duration::from_secs(Self::read(
+                    "QUOTES_FILE_CHECK_INTERVAL_SECONDS",
+                    5,
+                )?),
+                proxy: Self::read_proxy()?,
+                access_token_issuer: Self::read_access_token_issuer()?,
+                drain_timeout: Duration::from_secs(Self::read(
+                    "SHUTDOWN_DRAIN_TIMEOUT_SECONDS",
+                    30,
+                )?),
+                challenges_file_path: var_os("CHALLENGES_FILE_PATH").map(PathBuf::from),
+                challenge_lifetime: Self::read_timeout(
+                    "CHALLENGE_LIFETIME_SECONDS",
+                    ChallengeTable::DEFAULT_LIFETIME.as_secs(),
+                )?,
+                maximum_client_challenges_quantity,
+                deadlines: Deadlines {
+                    header_read: Self::read_timeout("HEADER_READ_TIMEOUT_SECONDS", 5)?,
+                    body_read: Self::read_timeout("BODY_READ_TIMEOUT_SECONDS", 10)?,
+                    request: Self::read_timeout("REQUEST_TIMEOUT_SECONDS", 30)?,
+                    response_write: Self::read_timeout("RESPONSE_WRITE_TIMEOUT_SECONDS", 10)?,
+                },
+                metrics_report_interval: Duration::from_secs(Self::read(
+                    "METRICS_REPORT_INTERVAL_SECONDS",
+                    60,
+                )?),
+                log_filter_file_path: var_os("LOG_FILTER_FILE_PATH").map(PathBuf::from),
+            });
+        }
+
+        fn read_difficulty(
+            name: &'static str,
+            default: Difficulty,
+        ) -> Result<Difficulty, Box<dyn Error + 'static>> {
+            let zero_bits = Self::read(name, default.zero_bits())?;
+
+            return match Difficulty::new(zero_bits) {
+                Ok(difficulty) => Ok(difficulty),
+                Err(_) => Err(Self::invalid_value(name)),
+            };
+        }
+
+        fn read_algorithms(
+            name: &'static str,
+            default: Algorithm,
+        ) -> Result<Vec<Algorithm>, Box<dyn Error + 'static>> {
+            let value = match var(name) {
+                Ok(value_) => value_,
+                Err(VarError::NotPresent) => {
+                    return Ok(vec![default]);
+                }
+                Err(error) => {
+                    return Err(Box::from(error));
+                }
+            };
+
+            let mut algorithms: Vec<Algorithm> = vec![];
+
+            for name_ in value.split(',') {
+                let algorithm = match name_.trim().parse::<Algorithm>() {
+                    Ok(algorithm_) => algorithm_,
+                    Err(_) => {
+                        return Err(Self::invalid_value(name));
+                    }
+                };
+
+                if !POW::is_supported(algorithm) {
+                    return Err(Self::invalid_value(name));
+                }
+
+                if !algorithms.contains(&algorithm) {
+                    algorithms.push(algorithm);
+                }
+            }
+
+            if algorithms.is_empty() {
+                return Err(Self::invalid_value(name));
+            }
+
+            return Ok(algorithms);
+        }
+
+        fn read_protected_resource() -> Result<Arc<dyn ProtectedResource>, Box<dyn Error + 'static>>
+        {
+            let strategy = Self::read("QUOTE_SELECTION", SelectionStrategy::Uniform)?;
+
+            let protected_resource: Arc<dyn ProtectedResource> =
+                match Self::read("PROTECTED_RESOURCE", String::from("quotes"))?.as_str() {
+                    "quotes" => Arc::new(QuoteList::built_in(strategy)),
+                    "quotes_file" => {
+                        let path = match var("QUOTES_FILE_PATH") {
+                            Ok(path_) => PathBuf::from(path_),
+                            Err(_) => {
+                                return Err(Self::invalid_value("QUOTES_FILE_PATH"));
+                            }
+                        };
+
+                        Arc::new(QuoteFile::open(path, strategy))
+                    }
+                    "upstream" => {
+                        let address = match var("UPSTREAM_ADDRESS") {
+                            Ok(address_) => address_,
+                            Err(_) => {
+                                return Err(Self::invalid_value("UPSTREAM_ADDRESS"));
+                            }
+                        };
+
+                        Arc::new(UpstreamForwarder {
+                            address,
+                            media_type: Self::read(
+                                "UPSTREAM_MEDIA_TYPE",
+                                String::from(UpstreamForwarder::DEFAULT_MEDIA_TYPE),
+                            )?,
+                            maximum_length: Self::read(
+                                "UPSTREAM_MAXIMUM_LENGTH",
+                                UpstreamForwarder::DEFAULT_MAXIMUM_LENGTH,
+                            )?,
+                        })
+                    }
+                    _ => {
+                        return Err(Self::invalid_value("PROTECTED_RESOURCE"));
+                    }
+                };
+
+            return Ok(protected_resource);
+        }
+
+        fn read_proxy() -> Result<Option<ProxyConfig>, Box<dyn Error + 'static>> {
+            let upstream_address = match var("PROXY_UPSTREAM_ADDRESS") {
+                Ok(upstream_address_) => upstream_address_,
+                Err(VarError::NotPresent) => {
+                    return Ok(None);
+                }
+                Err(error) => {
+                    return Err(Box::from(error));
+                }
+            };
+
+            let ticket_lifetime_seconds = Self::read("PROXY_TICKET_LIFETIME_SECONDS", 30)?;
+
+            if ticket_lifetime_seconds == 0 {
+                return Err(Self::invalid_value("PROXY_TICKET_LIFETIME_SECONDS"));
+            }
+
+            return Ok(Some(ProxyConfig {
+                upstream_address,
+                ticket_lifetime: Duration::from_secs(ticket_lifetime_seconds),
+            }));
+        }
+
+        fn read_timeout(
+            name: &'static str,
+            default: u64,
+        ) -> Result<Duration, Box<dyn Error + 'static>> {
+            let seconds = Self::read(name, default)?;
+
+            if seconds == 0 {
+                return Err(Self::invalid_value(name));
+            }
+
+            return Ok(Duration::from_secs(seconds));
+        }
+
+        fn read_access_token_issuer() -> Result<Option<AccessTokenIssuer>, Box<dyn Error + 'static>>
+        {
+            if !Self::read("ACCESS_TOKENS_ENABLED", false)? {
+                return Ok(None);
+            }
+
+            // The generated key is not kept, so, the tokens are not accepted after the restart.
+            let key = match var("ACCESS_TOKEN_KEY") {
+                Ok(key_) => match Self::decode_hex(key_.trim()) {
+                    Some(key__) if key__.len() >= AccessTokenIssuer::KEY_LENGTH => key__,
+                    _ => {
+                        return Err(Self::invalid_value("ACCESS_TOKEN_KEY"));
+                    }
+                },
+                Err(VarError::NotPresent) => AccessTokenIssuer::generate_key(),
+                Err(error) => {
+                    return Err(Box::from(error));
+                }
+            };
+
+            let lifetime_seconds = Self::read(
+                "ACCESS_TOKEN_LIFETIME_SECONDS",
+                AccessTokenIssuer::DEFAULT_LIFETIME.as_secs(),
+            )?;
+
+            if lifetime_seconds == 0 {
+                return Err(Self::invalid_value("ACCESS_TOKEN_LIFETIME_SECONDS"));
+            }
+
+            let requests_quantity = Self::read(
+                "ACCESS_TOKEN_REQUESTS_QUANTITY",
+                AccessTokenIssuer::DEFAULT_REQUESTS_QUANTITY,
+            )?;
+
+            if requests_quantity == 0 {
+                return Err(Self::invalid_value("ACCESS_TOKEN_REQUESTS_QUANTITY"));
+            }
+
+            return Ok(Some(AccessTokenIssuer::new(
+                key,
+                Duration::from_secs(lifetime_seconds),
+                requests_quantity,
+            )));
+        }
+
+        fn decode_hex<'a>(value: &'a str) -> Option<Vec<u8>> {
+            if !value.len().is_multiple_of(2) {
+                return None;
+            }
+
+            return (0..value.len())
+                .step_by(2)
+                .map(|index| {
+                    return u8::from_str_radix(value.get(index..index + 2)?, 16).ok();
+                })
+                .collect();
+        }
+
+        fn read<T>(name: &'static str, default: T) -> Result<T, Box<dyn Error + 'static>>
+        where
+            T: FromStr,
+        {
+            let value = match var(name) {
+                Ok(value_) => value_,
+                Err(VarError::NotPresent) => {
+                    return Ok(default);
+                }
+                Err(error) => {
+                    return Err(Box::from(error));
+                }
+            };
+
+            return match value.trim().parse::<T>() {
+                Ok(value_) => Ok(value_),
+                Err(_) => Err(Self::invalid_value(name)),
+            };
+        }
+
+        fn invalid_value(name: &'static str) -> Box<dyn Error + 'static> {
+            return Box::from(format!(
+                "Invalid value of the {} environment variable.",
+                name
+            ));
+        }
+    }
+
+    // The request is read, handled and answered within the request timeout, and every of its phases within its own one.
+    #[derive(Clone, Copy)]
+    pub struct Deadlines {
+        pub header_read: Duration,
+        pub body_read: Duration,
+        pub request: Duration,
+        pub response_write: Duration,
+    }
+
+    pub struct ProofOfWorkConfig {
+        // In the order of preference.
+        pub algorithms: Vec<Algorithm>,
+        // The difficulty is split between the puzzles.
+        pub puzzles_quantity: u16,
+        pub memory_hard_parameters: MemoryHardParameters,
+        // The quantity of zero bits, by which the decided difficulty is lowered for the memory-hard algorithm,
+        // so, that the server verifies a single memory-hard hash against the relaxed target.
+        pub memory_hard_difficulty_relaxation: u8,
+        pub generalized_birthday_parameters: GeneralizedBirthdayParameters,
+        // The same for the Equihash, every solution of which costs the whole run of the Wagner algorithm.
+        pub generalized_birthday_difficulty_relaxation: u8,
+        // With the trapdoor, if the time-lock puzzle is enabled. The difficulty is not applied to it.
+        pub sequential_squaring_parameters: SequentialSquaringParameters,
+    }
+
+    impl ProofOfWorkConfig {
+        pub fn parameters<'a>(&'a self, algorithm: Algorithm) -> Parameters {
+            return match algorithm {
+                Algorithm::Argon2id => Parameters::MemoryHard(self.memory_hard_parameters),
+                Algorithm::Equihash => {
+                    Parameters::GeneralizedBirthday(self.generalized_birthday_parameters)
+                }
+                Algorithm::TimeLock => {
+                    Parameters::SequentialSquaring(self.sequential_squaring_parameters.clone())
+                }
+                _ => Parameters::default_for(algorithm),
+            };
+        }
+
+        pub fn puzzles_quantity<'a>(&'a self, algorithm: Algorithm) -> u16 {
+            return match algorithm {
+                Algorithm::TimeLock => 1,
+                _ => self.puzzles_quantity,
+            };
+        }
+
+        pub fn difficulty<'a>(
+            &'a self,
+            algorithm: Algorithm,
+            difficulty: Difficulty,
+        ) -> Difficulty {
+            let difficulty_ = difficulty.split(self.puzzles_quantity(algorithm));
+
+            return match algorithm {
+                Algorithm::Argon2id => difficulty_.lower(self.memory_hard_difficulty_relaxation),
+                Algorithm::Equihash => {
+                    difficulty_.lower(self.generalized_birthday_difficulty_relaxation)
+                }
+                _ => difficulty_,
+            };
+        }
+    }
+}
+
+mod difficulty_controller {
+    use infrastructure::Difficulty;
+    use std::collections::HashMap;
+    use std::collections::VecDeque;
+    use std::fmt::Display;
+    use std::fmt::Formatter;
+    use std::fmt::Result as FmtResult;
+    use std::net::IpAddr;
+    use std::sync::Mutex;
+    use std::sync::MutexGuard;
+    use std::sync::PoisonError;
+    use std::time::Duration;
+    use std::time::Instant;
+
+    pub struct DifficultyControllerConfig {
+        pub minimum: Difficulty,
+        pub maximum: Difficulty,
+        pub baseline: Difficulty,
+        // The quantity of zero bits added to the baseline per each unit of pressure.
+        pub step: u8,
+        pub window: Duration,
+        // Every full multiple of a threshold reached within the window is one unit of pressure.
+        pub requests_threshold: usize,
+        // Every full multiple of it reached by the outstanding challenges is one unit of pressure.
+        pub outstanding_challenges_threshold: usize,
+        pub client_requests_threshold: usize,
+    }
+
+    // Raises the announced difficulty above the baseline while the server is under pressure
+    // and relaxes it back as soon as the observed requests leave the sliding window.
+    pub struct DifficultyController {
+        config: DifficultyControllerConfig,
+        history: Mutex<History>,
+    }
+
+    impl DifficultyController {
+        pub fn new(config: DifficultyControllerConfig) -> Self {
+            let now = Instant::now();
+
+            return Self {
+                config,
+                history: Mutex::new(History {
+                    requests: VecDeque::new(),
+                    clients: HashMap::new(),
+                    last_sweep: now,
+                }),
+            };
+        }
+
+        pub fn observe<'a>(&'a self, client: IpAddr) -> () {
+            let now = Instant::now();
+
+            let mut mutex_guard = self.lock_history();
+
+            mutex_guard.forget(now, self.config.window);
+
+            mutex_guard.requests.push_back(now);
+
+            mutex_guard
+                .clients
+                .entry(client)
+                .or_default()
+                .push_back(now);
+
+            return ();
+        }
+
+        // The outstanding challenges are the challenges of the table, that are neither redeemed nor expired.
+        // They expire after the lifetime and are limited per client, so, the challenges, that are never redeemed,
+        // keep the difficulty raised for the lifetime at most.
+        pub fn decide<'a>(
+            &'a self,
+            client: IpAddr,
+            outstanding_challenges_quantity: usize,
+        ) -> Decision {
+            let now = Instant::now();
+
+            let (requests_quantity, client_requests_quantity) = {
+                let mut mutex_guard = self.lock_history();
+
+                mutex_guard.forget(now, self.config.window);
+
+                let client_requests_quantity = match mutex_guard.clients.get(&client) {
+                    Some(client_requests) => client_requests.len(),
+                    None => 0,
+                };
+
+                (mutex_guard.requests.len(), client_requests_quantity)
+            };
+
+            let pressure = Self::pressure(requests_quantity, self.config.requests_threshold)
+                .saturating_add(Self::pressure(
+                    outstanding_challenges_quantity,
+                    self.config.outstanding_challenges_threshold,
+                ))
+                .saturating_add(Self::pressure(
+                    client_requests_quantity,
+                    self.config.client_requests_threshold,
+                ));
+
+            let raise =
+                u8::try_from(pressure.saturating_mul(self.config.step as u32)).unwrap_or(u8::MAX);
+
+            let difficulty = self
+                .config
+                .baseline
+                .raise(raise)
+                .clamp(self.config.minimum, self.config.maximum);
+
+            return Decision {
+                client,
+                difficulty,
+                pressure,
+                requests_quantity,
+                outstanding_challenges_quantity,
+                client_requests_quantity,
+            };
+        }
+
+        // The history stays consistent after the panic of another thread, so, the poisoned lock is recovered.
+        fn lock_history<'a>(&'a self) -> MutexGuard<'a, History> {
+            return self.history.lock().unwrap_or_else(PoisonError::into_inner);
+        }
+
+        fn pressure(observed_quantity: usize, threshold: usize) -> u32 {
+            if threshold == 0 {
+                return 0;
+            }
+
+            return u32::try_from(observed_quantity / threshold).unwrap_or(u32::MAX);
+        }
+    }
+
+    struct History {
+        requests: VecDeque<Instant>,
+        clients: HashMap<IpAddr, VecDeque<Instant>>,
+        last_sweep: Instant,
+    }
+
+    impl History {
+        fn forget<'a>(&'a mut self, now: Instant, window: Duration) -> () {
+            let horizon = match now.checked_sub(window) {
+                Some(horizon_) => horizon_,
+                None => {
+                    return ();
+                }
+            };
+
+            Self::forget_(&mut self.requests, horizon);
+
+            if now.duration_since(self.last_sweep) >= window {
+                self.clients.retain(|_, client_requests| {
+                    Self::forget_(client_requests, horizon);
+
+                    !client_requests.is_empty()
+                });
+
+                self.last_sweep = now;
+            }
+
+            return ();
+        }
+
+        fn forget_<'a>(requests: &'a mut VecDeque<Instant>, horizon: Instant) -> () {
+            'a: while let Some(request) = requests.front() {
+                if *request >= horizon {
+                    break 'a;
+                }
+
+                requests.pop_front();
+            }
+
+            return ();
+        }
+    }
+
+    pub struct Decision {
+        pub client: IpAddr,
+        pub difficulty: Difficulty,
+        pub pressure: u32,
+        pub requests_quantity: usize,
+        pub outstanding_challenges_quantity: usize,
+        pub client_requests_quantity: usize,
+    }
+
+    impl Display for Decision {
+        fn fmt<'a>(&'a self, formatter: &'a mut Formatter<'_>) -> FmtResult {
+            write!(
+                formatter,
+                "Difficulty decision: client {}, requests {}, outstanding challenges {}, client requests {}, pressure {}, difficulty {} zero bits.",
+                &self.client,
+                self.requests_quantity,
+                self.outstanding_challenges_quantity,
+                self.client_requests_quantity,
+                self.pressure,
+                self.difficulty.zero_bits(),
+            )
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::DifficultyController;
+        use super::DifficultyControllerConfig;
+        use infrastructure::Difficulty;
+        use std::net::IpAddr;
+        use std::net::Ipv4Addr;
+        use std::thread::sleep;
+        use std::time::Duration;
+
+        const CLIENT: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+        fn config() -> DifficultyControllerConfig {
+            return DifficultyControllerConfig {
+                minimum: Difficulty::I,
+                maximum: Difficulty::IV,
+                baseline: Difficulty::II,
+                step: 4,
+                window: Duration::from_secs(60),
+                requests_threshold: 4,
+                outstanding_challenges_threshold: 10,
+                client_requests_threshold: 2,
+            };
+        }
+
+        #[test]
+        fn difficulty_is_baseline_without_pressure() -> () {
+            let decision = DifficultyController::new(config()).decide(CLIENT, 9);
+
+            assert_eq!(decision.pressure, 0);
+
+            assert_eq!(decision.difficulty, Difficulty::II);
+
+            return ();
+        }
+
+        #[test]
+        fn difficulty_is_raised_by_outstanding_challenges() -> () {
+            let difficulty_controller = DifficultyController::new(config());
+
+            let decision = difficulty_controller.decide(CLIENT, 10);
+
+            assert_eq!(decision.pressure, 1);
+
+            assert_eq!(decision.difficulty.zero_bits(), 20);
+
+            let decision_ = difficulty_controller.decide(CLIENT, 29);
+
+            assert_eq!(decision_.pressure, 2);
+
+            assert_eq!(decision_.difficulty.zero_bits(), 24);
+
+            return ();
+        }
+
+        #[test]
+        fn difficulty_is_raised_by_requests() -> () {
+            let difficulty_controller = DifficultyController::new(config());
+
+            difficulty_controller.observe(CLIENT);
+
+            difficulty_controller.observe(CLIENT);
+
+            difficulty_controller.observe(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)));
+
+            difficulty_controller.observe(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 3)));
+
+            // The requests and the requests of the client reach their thresholds.
+            let decision = difficulty_controller.decide(CLIENT, 0);
+
+            assert_eq!(decision.requests_quantity, 4);
+
+            assert_eq!(decision.client_requests_quantity, 2);
+
+            assert_eq!(decision.pressure, 2);
+
+            assert_eq!(decision.difficulty.zero_bits(), 24);
+
+            // The other client is raised by all requests only.
+            let decision_ =
+                difficulty_controller.decide(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 0);
+
+            assert_eq!(decision_.pressure, 1);
+
+            assert_eq!(decision_.difficulty.zero_bits(), 20);
+
+            return ();
+        }
+
+        #[test]
+        fn difficulty_relaxes_to_baseline() -> () {
+            let mut config_ = config();
+
+            config_.window = Duration::from_millis(50);
+
+            let difficulty_controller = DifficultyController::new(config_);
+
+            for _ in 0..8 {
+                difficulty_controller.observe(CLIENT);
+            }
+
+            assert!(difficulty_controller.decide(CLIENT, 0).difficulty > Difficulty::II);
+
+            sleep(Duration::from_millis(100));
+
+            let decision = difficulty_controller.decide(CLIENT, 0);
+
+            assert_eq!(decision.requests_quantity, 0);
+
+            assert_eq!(decision.client_requests_quantity, 0);
+
+            assert_eq!(decision.difficulty, Difficulty::II);
+
+            return ();
+        }
+
+        #[test]
+        fn difficulty_is_clamped() -> () {
+            let difficulty_controller = DifficultyController::new(config());
+
+            assert_eq!(
+                difficulty_controller.decide(CLIENT, 1000).difficulty,
+                Difficulty::IV
+            );
+
+            assert_eq!(
+                difficulty_controller.decide(CLIENT, usize::MAX).difficulty,
+                Difficulty::IV
+            );
+
+            let mut config_ = config();
+
+            config_.minimum = Difficulty::III;
+
+            config_.step = u8::MAX;
+
+            let difficulty_controller_ = DifficultyController::new(config_);
+
+            assert_eq!(
+                difficulty_controller_.decide(CLIENT, 0).difficulty,
+                Difficulty::III
+            );
+
+            assert_eq!(
+                difficulty_controller_.decide(CLIENT, 10).difficulty,
+                Difficulty::IV
+            );
+
+            return ();
+        }
+    }
+}
+
+mod protected_resource {
+    use infrastructure::rand::seq::SliceRandom;
+    use infrastructure::rand::thread_rng;
+    use infrastructure::rand::Rng;
+    use infrastructure::tokio::io::AsyncReadExt;
+    use infrastructure::tokio::net::TcpStream;
+    use infrastructure::tokio::select;
+    use infrastructure::tokio::signal::unix::signal;
+    use infrastructure::tokio::signal::unix::SignalKind;
+    use infrastructure::tokio::time::interval;
+    use infrastructure::tracing::error;
+    use infrastructure::tracing::info;
+    use infrastructure::tracing::warn;
+    use infrastructure::ErrorKind;
+    use infrastructure::Payload;
+    use infrastructure::Quote;
+    use infrastructure::QuoteFilter;
+    use infrastructure::WORD_OF_WISDOM_QUOTES;
+    use serde::Deserialize;
+    use serde_json::from_str;
+    use std::borrow::Cow;
+    use std::collections::BTreeMap;
+    use std::collections::HashMap;
+    use std::error::Error;
+    use std::ffi::OsStr;
+    use std::fs::metadata;
+    use std::fs::read_to_string;
+    use std::future::Future;
+    use std::net::IpAddr;
+    use std::path::PathBuf;
+    use std::pin::Pin;
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use std::sync::PoisonError;
+    use std::sync::RwLock;
+    use std::sync::RwLockReadGuard;
+    use std::sync::RwLockWriteGuard;
+    use std::time::Duration;
+    use std::time::SystemTime;
+    use std::time::UNIX_EPOCH;
+
+    // Is None, if there is no resource, that matches the filter.
+    pub type Provision<'a> = Pin<
+        Box<
+            dyn Future<Output = Result<Option<Payload<'static>>, Box<dyn Error + 'static>>>
+                + Send
+                + 'a,
+        >,
+    >;
+
+    // The resource, that is protected by the proof of work. Is provided after the successful verification.
+    // The client and the filter of the quotes are ignored by the resources, that are not quotes.
+    pub trait ProtectedResource: Send + Sync {
+        fn provide<'a>(&'a self, client: IpAddr, filter: &'a QuoteFilter) -> Provision<'a>;
+
+        // Reloads the resource, if it is changed, or anyway, if it is forced. Returns whether it is reloaded.
+        fn refresh<'a>(&'a self, _is_forced: bool) -> Result<bool, Box<dyn Error + 'static>> {
+            return Ok(false);
+        }
+    }
+
+    // Refreshes the resource periodically and on the SIGHUP, which forces it.
+    pub async fn watch(protected_resource: Arc<dyn ProtectedResource>, period: Duration) -> () {
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(hangup_) => hangup_,
+            Err(error) => {
+                error!("{}", &error);
+
+                return ();
+            }
+        };
+
+        let mut interval_ = interval(period);
+
+        interval_.tick().await;
+
+        loop {
+            let is_forced = select! {
+                _ = interval_.tick() => false,
+                _ = hangup.recv() => true,
+            };
+
+            if let Err(error) = protected_resource.refresh(is_forced) {
+                warn!("{} The loaded resource is kept.", &error);
+            }
+        }
+    }
+
+    // The quote of the collection with the priority of the weighted selection.
+    #[derive(Debug, Clone, Deserialize)]
+    pub struct QuoteEntry {
+        #[serde(flatten)]
+        pub quote: Quote<'static>,
+        #[serde(default = "QuoteEntry::default_priority")]
+        pub priority: u32,
+    }
+
+    impl QuoteEntry {
+        fn new(quote: Quote<'static>) -> Self {
+            return Self {
+                quote,
+                priority: Self::default_priority(),
+            };
+        }
+
+        fn default_priority() -> u32 {
+            return 1;
+        }
+    }
+
+    // How the quote is chosen among the matching quotes.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum SelectionStrategy {
+        // The random quote.
+        Uniform,
+        // The quotes of the shuffled deck of the client, so, the quote is not repeated, until the deck is exhausted.
+        Deck,
+        // The random quote with the probability, that is proportional to its priority.
+        Weighted,
+        // The same quote during the whole UTC day.
+        Daily,
+    }
+
+    impl FromStr for SelectionStrategy {
+        type Err = ErrorKind;
+
+        fn from_str<'a>(name: &'a str) -> Result<Self, Self::Err> {
+            return match name {
+                "uniform" => Ok(Self::Uniform),
+                "deck" => Ok(Self::Deck),
+                "weighted" => Ok(Self::Weighted),
+                "daily" => Ok(Self::Daily),
+                _ => Err(ErrorKind::Logic),
+            };
+        }
+    }
+
+    pub struct QuoteList {
+        entries: Vec<QuoteEntry>,
+        strategy: SelectionStrategy,
+        decks: Mutex<Decks>,
+    }
+
+    // Of the clients, which are identified by the address. The least recently used deck is forgotten,
+    // if there are too many.
+    #[derive(Default)]
+    struct Decks {
+        decks: HashMap<IpAddr, Deck>,
+        // The clients in the order of the use, so, the least recently used one is found without the scan.
+        uses: BTreeMap<u64, IpAddr>,
+        last_use: u64,
+    }
+
+    struct Deck {
+        // The quotes before the position are drawn.
+        order: Vec<usize>,
+        position: usize,
+        // The key of the client in the uses.
+        last_use: u64,
+    }
+
+    impl QuoteList {
+        const MAXIMUM_DECKS_QUANTITY: usize = 65536;
+        const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+        // Every quote has the text and the positive priority, the metadata is not blank.
+        pub fn new(
+            entries: Vec<QuoteEntry>,
+            strategy: SelectionStrategy,
+        ) -> Result<Self, Box<dyn Error + 'static>> {
+            if entries.is_empty() {
+                return Err(Box::from("The quote list is empty."));
+            }
+
+            for entry in entries.iter() {
+                if entry.quote.text.trim().is_empty()
+                    || [
+                        &entry.quote.author,
+                        &entry.quote.source,
+                        &entry.quote.language,
+                    ]
+                    .into_iter()
+                    .flatten()
+                    .chain(entry.quote.tags.iter())
+                    .any(|value| value.trim().is_empty())
+                {
+                    return Err(Box::from(
+                        "The quote list contains the blank text, author, source, language or tag.",
+                    ));
+                }
+
+                if entry.priority == 0 {
+                    return Err(Box::from("The quote list contains the zero priority."));
+                }
+            }
+
+            return Ok(Self {
+                entries,
+                strategy,
+                decks: Mutex::new(Decks::default()),
+            });
+        }
+
+        pub fn built_in(strategy: SelectionStrategy) -> Self {
+            return Self {
+                entries: WORD_OF_WISDOM_QUOTES
+                    .iter()
+                    .map(|quote| {
+                        let mut quote_ = Quote::new(Cow::Borrowed(*quote));
+
+                        quote_.language = Some(Cow::Borrowed("en"));
+
+                        QuoteEntry::new(quote_)
+                    })
+                    .collect(),
+                strategy,
+                decks: Mutex::new(Decks::default()),
+            };
+        }
+
+        pub fn len<'a>(&'a self) -> usize {
+            return self.entries.len();
+        }
+
+        fn choose<'a>(
+            &'a self,
+            client: IpAddr,
+            filter: &'a QuoteFilter,
+        ) -> Option<Payload<'static>> {
+            let index = if self.strategy == SelectionStrategy::Deck {
+                self.draw(client, filter)?
+            } else {
+                let matching_indices: Vec<usize> = (0..self.entries.len())
+                    .filter(|index| filter.matches(&self.entries[*index].quote))
+                    .collect();
+
+                if matching_indices.is_empty() {
+                    return None;
+                }
+
+                match self.strategy {
+                    SelectionStrategy::Weighted => {
+                        let total_priority: u64 = matching_indices
+                            .iter()
+                            .map(|index| self.entries[*index].priority as u64)
+                            .sum();
+
+                        let mut point = thread_rng().gen_range::<u64, _>(0..total_priority);
+
+                        let mut index_ = matching_indices[matching_indices.len() - 1];
+
+                        'a: for index__ in matching_indices.iter() {
+                            let priority = self.entries[*index__].priority as u64;
+
+                            if point < priority {
+                                index_ = *index__;
+
+                                break 'a;
+                            }
+
+                            point -= priority;
+                        }
+
+                        index_
+                    }
+                    SelectionStrategy::Daily => {
+                        let day = match SystemTime::now().duration_since(UNIX_EPOCH) {
+                            Ok(duration) => duration.as_secs() / Self::SECONDS_PER_DAY,
+                            Err(_) => 0,
+                        };
+
+                        // The neighbouring days get the unrelated quotes.
+                        let hash = day.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 32;
+
+                        matching_indices[(hash as usize) % matching_indices.len()]
+                    }
+                    SelectionStrategy::Uniform | SelectionStrategy::Deck => {
+                        matching_indices
+                            [thread_rng().gen_range::<usize, _>(0..matching_indices.len())]
+                    }
+                }
+            };
+
+            return Some(Payload::Quote {
+                quote: self.entries[index].quote.clone(),
+            });
+        }
+
+        // The next matching quote of the deck. The exhausted deck is shuffled anew. The deck is not touched,
+        // if there is no matching quote at all.
+        fn draw<'a>(&'a self, client: IpAddr, filter: &'a QuoteFilter) -> Option<usize> {
+            let is_matching = |index: &usize| filter.matches(&self.entries[*index].quote);
+
+            if !(0..self.entries.len()).any(|index| is_matching(&index)) {
+                return None;
+            }
+
+            let mut decks = self.decks.lock().unwrap_or_else(PoisonError::into_inner);
+
+            let deck = decks.use_deck(client, self.entries.len());
+
+            if let Some(index) = deck.draw(is_matching) {
+                return Some(index);
+            }
+
+            deck.order.shuffle(&mut thread_rng());
+
+            deck.position = 0;
+
+            return deck.draw(is_matching);
+        }
+    }
+
+    impl Decks {
+        // The deck of the new client is exhausted, so, it is shuffled on the first draw.
+        fn use_deck<'a>(&'a mut self, client: IpAddr, quotes_quantity: usize) -> &'a mut Deck {
+            self.last_use += 1;
+
+            if let Some(deck) = self.decks.get(&client) {
+                self.uses.remove(&deck.last_use);
+            } else if self.decks.len() >= QuoteList::MAXIMUM_DECKS_QUANTITY {
+                if let Some((_, least_recently_used_client)) = self.uses.pop_first() {
+                    self.decks.remove(&least_recently_used_client);
+                }
+            }
+
+            self.uses.insert(self.last_use, client);
+
+            let deck = self.decks.entry(client).or_insert_with(|| Deck {
+                order: (0..quotes_quantity).collect(),
+                position: quotes_quantity,
+                last_use: 0,
+            });
+
+            deck.last_use = self.last_use;
+
+            return deck;
+        }
+    }
+
+    impl Deck {
+        fn draw<F>(&mut self, is_matching: F) -> Option<usize>
+        where
+            F: Fn(&usize) -> bool,
+        {
+            let offset = self.order[self.position..].iter().position(is_matching)?;
+
+            self.order.swap(self.position, self.position + offset);
+
+            self.position += 1;
+
+            return Some(self.order[self.position - 1]);
+        }
+    }
+
+    impl ProtectedResource for QuoteList {
+        fn provide<'a>(&'a self, client: IpAddr, filter: &'a QuoteFilter) -> Provision<'a> {
+            let payload = self.choose(client, filter);
+
+            return Box::pin(async move {
+                return Ok(payload);
+            });
+        }
+    }
+
+    // The random quote of the file, that is reloaded, if it is changed. The format depends on the extension:
+    // the JSON array of the texts or the quotes (.json), the quote entries (.toml), or one text per line (otherwise).
+    // The invalid file does not replace the loaded quotes, and the built-in quotes are the fallback,
+    // until the file is loaded.
+    pub struct QuoteFile {
+        path: PathBuf,
+        strategy: SelectionStrategy,
+        state: RwLock<QuoteFileState>,
+    }
+
+    struct QuoteFileState {
+        quote_list: QuoteList,
+        // Of the last read file, so, the invalid file is not read again, until it is changed.
+        modified: Option<SystemTime>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum JsonQuote {
+        Text(String),
+        Quote(QuoteEntry),
+    }
+
+    #[derive(Deserialize)]
+    struct TomlQuotes {
+        quote: Vec<QuoteEntry>,
+    }
+
+    impl QuoteFile {
+        pub fn open(path: PathBuf, strategy: SelectionStrategy) -> Self {
+            let quote_file = Self {
+                path,
+                strategy,
+                state: RwLock::new(QuoteFileState {
+                    quote_list: QuoteList::built_in(strategy),
+                    modified: None,
+                }),
+            };
+
+            if let Err(error) = quote_file.refresh(true) {
+                warn!("{} The built-in quotes are served.", &error);
+            }
+
+            return quote_file;
+        }
+
+        fn parse<'a>(&'a self, content: &'a str) -> Result<QuoteList, Box<dyn Error + 'static>> {
+            let entries: Vec<QuoteEntry> = match self.path.extension().and_then(OsStr::to_str) {
+                Some("json") => match from_str::<Vec<JsonQuote>>(content) {
+                    Ok(json_quotes) => json_quotes
+                        .into_iter()
+                        .map(|json_quote| match json_quote {
+                            JsonQuote::Text(text) => QuoteEntry::new(Quote::new(Cow::Owned(text))),
+                            JsonQuote::Quote(entry) => entry,
+                        })
+                        .collect(),
+                    Err(error) => {
+                        return Err(Box::from(error));
+                    }
+                },
+                Some("toml") => match toml::from_str::<TomlQuotes>(content) {
+                    Ok(toml_quotes) => toml_quotes.quote,
+                    Err(error) => {
+                        return Err(Box::from(error));
+                    }
+                },
+                _ => content
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(|line| QuoteEntry::new(Quote::new(Cow::Owned(String::from(line)))))
+                    .collect(),
+            };
+
+            return QuoteList::new(entries, self.strategy);
+        }
+
+        fn read_state<'a>(&'a self) -> RwLockReadGuard<'a, QuoteFileState> {
+            return self.state.read().unwrap_or_else(PoisonError::into_inner);
+        }
+
+        fn write_state<'a>(&'a self) -> RwLockWriteGuard<'a, QuoteFileState> {
+            return self.state.write().unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    impl ProtectedResource for QuoteFile {
+        fn provide<'a>(&'a self, client: IpAddr, filter: &'a QuoteFilter) -> Provision<'a> {
+            let payload = self.read_state().quote_list.choose(client, filter);
+
+            return Box::pin(async move {
+                return Ok(payload);
+            });
+        }
+
+        fn refresh<'a>(&'a self, is_forced: bool) -> Result<bool, Box<dyn Error + 'static>> {
+            let modified =
+                match metadata(self.path.as_path()).and_then(|metadata_| metadata_.modified()) {
+                    Ok(modified_) => Some(modified_),
+                    Err(error) => {
+                        return Err(Box::from(format!(
+                            "The quote file {} is not read: {}",
+                            self.path.display(),
+                            &error
+                        )));
+                    }
+                };
+
+            if !is_forced && modified == self.read_state().modified {
+                return Ok(false);
+            }
+
+            self.write_state().modified = modified;
+
+            let content = match read_to_string(self.path.as_path()) {
+                Ok(content_) => content_,
+                Err(error) => {
+                    return Err(Box::from(format!(
+                        "The quote file {} is not read: {}",
+                        self.path.display(),
+                        &error
+                    )));
+                }
+            };
+
+            let quote_list = match self.parse(content.as_str()) {
+                Ok(quote_list_) => quote_list_,
+                Err(error) => {
+                    return Err(Box::from(format!(
+                        "The quote file {} is invalid: {}",
+                        self.path.display(),
+                        &error
+                    )));
+                }
+            };
+
+            info!(
+                "The quote file {} is loaded: {} quotes.",
+                self.path.display(),
+                quote_list.len()
+            );
+
+            self.write_state().quote_list = quote_list;
+
+            return Ok(true);
+        }
+    }
+
+    // The resource of the upstream service, that writes it and closes the connection (as the Quote of the Day service does).
+    pub struct UpstreamForwarder {
+        pub address: String,
+        pub media_type: String,
+        // The longer resource is not forwarded.
+        pub maximum_length: u64,
+    }
+
+    impl UpstreamForwarder {
+        pub const DEFAULT_MEDIA_TYPE: &'static str = "application/octet-stream";
+        pub const DEFAULT_MAXIMUM_LENGTH: u64 = 131072;
+    }
+
+    impl ProtectedResource for UpstreamForwarder {
+        fn provide<'a>(&'a self, _client: IpAddr, _filter: &'a QuoteFilter) -> Provision<'a> {
+            return Box::pin(async move {
+                let tcp_stream = match TcpStream::connect(self.address.as_str()).await {
+                    Ok(tcp_stream_) => tcp_stream_,
+                    Err(error) => {
+                        return Err(Box::from(error));
+                    }
+                };
+
+                let mut data: Vec<u8> = vec![];
+
+                if let Err(error) = tcp_stream
+                    .take(self.maximum_length.saturating_add(1))
+                    .read_to_end(&mut data)
+                    .await
+                {
+                    return Err(Box::from(error));
+                }
+
+                if (data.len() as u64) > self.maximum_length {
+                    return Err(Box::from(ErrorKind::RunTime));
+                }
+
+                return Ok(Some(Payload::Binary {
+                    media_type: Cow::Owned(self.media_type.clone()),
+                    data: Cow::Owned(data),
+                }));
+            });
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::Decks;
+        use super::ProtectedResource;
+        use super::QuoteFile;
+        use super::QuoteList;
+        use super::SelectionStrategy;
+        use infrastructure::uuid::Uuid;
+        use infrastructure::QuoteFilter;
+        use infrastructure::WORD_OF_WISDOM_QUOTES;
+        use std::collections::HashSet;
+        use std::env::temp_dir;
+        use std::fs::remove_file;
+        use std::fs::write;
+        use std::fs::File;
+        use std::net::IpAddr;
+        use std::net::Ipv4Addr;
+        use std::path::Path;
+        use std::path::PathBuf;
+        use std::time::Duration;
+        use std::time::SystemTime;
+
+        const CLIENT: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+        fn quote_file_path<'a>(extension: &'a str) -> PathBuf {
+            return temp_dir().join(format!("quotes_{}.{}", Uuid::new_v4(), extension));
+        }
+
+        // The modification time is set explicitly, so, the change is seen regardless of the precision of the file system.
+        fn rewrite<'a>(path: &'a Path, content: &'a str, modified: SystemTime) -> () {
+            write(path, content).unwrap();
+
+            File::options()
+                .write(true)
+                .open(path)
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+
+            return ();
+        }
+
+        fn texts<'a>(quote_file: &'a QuoteFile) -> Vec<String> {
+            return quote_file
+                .read_state()
+                .quote_list
+                .entries
+                .iter()
+                .map(|entry| entry.quote.text.to_string())
+                .collect();
+        }
+
+        #[test]
+        fn quotes_are_parsed_from_lines() -> () {
+            let path = quote_file_path("txt");
+
+            write(path.as_path(), "First.\n\n  Second.  \n").unwrap();
+
+            let quote_file = QuoteFile::open(path.clone(), SelectionStrategy::Uniform);
+
+            let _ = remove_file(path.as_path());
+
+            assert_eq!(texts(&quote_file), vec!["First.", "Second."]);
+
+            return ();
+        }
+
+        #[test]
+        fn quotes_are_parsed_from_json() -> () {
+            let path = quote_file_path("json");
+
+            write(
+                path.as_path(),
+                r#"["First.", {"text": "Second.", "author": "Author", "tags": ["tag"], "priority": 3}]"#,
+            )
+            .unwrap();
+
+            let quote_file = QuoteFile::open(path.clone(), SelectionStrategy::Weighted);
+
+            let _ = remove_file(path.as_path());
+
+            assert_eq!(texts(&quote_file), vec!["First.", "Second."]);
+
+            let state = quote_file.read_state();
+
+            let entry = &state.quote_list.entries[1];
+
+            assert_eq!(entry.quote.author.as_deref(), Some("Author"));
+
+            assert_eq!(entry.quote.tags, vec!["tag"]);
+
+            assert_eq!(entry.priority, 3);
+
+            assert_eq!(state.quote_list.entries[0].priority, 1);
+
+            return ();
+        }
+
+        #[test]
+        fn quotes_are_parsed_from_toml() -> () {
+            let path = quote_file_path("toml");
+
+            write(
+                path.as_path(),
+                "[[quote]]\ntext = \"First.\"\n\n[[quote]]\ntext = \"Second.\"\nauthor = \"Author\"\nlanguage = \"en\"\ntags = [\"tag\"]\npriority = 2\n",
+            )
+            .unwrap();
+
+            let quote_file = QuoteFile::open(path.clone(), SelectionStrategy::Uniform);
+
+            let _ = remove_file(path.as_path());
+
+            assert_eq!(texts(&quote_file), vec!["First.", "Second."]);
+
+            let state = quote_file.read_state();
+
+            let entry = &state.quote_list.entries[1];
+
+            assert_eq!(entry.quote.author.as_deref(), Some("Author"));
+
+            assert_eq!(entry.quote.language.as_deref(), Some("en"));
+
+            assert_eq!(entry.quote.tags, vec!["tag"]);
+
+            assert_eq!(entry.priority, 2);
+
+            return ();
+        }
+
+        #[test]
+        fn invalid_file_keeps_loaded_quotes() -> () {
+            let path = quote_file_path("json");
+
+            write(path.as_path(), "{").unwrap();
+
+            // The built-in quotes are served, until the file is loaded.
+            let quote_file = QuoteFile::open(path.clone(), SelectionStrategy::Uniform);
+
+            assert_eq!(texts(&quote_file).len(), WORD_OF_WISDOM_QUOTES.len());
+
+            let now = SystemTime::now();
+
+            rewrite(
+                path.as_path(),
+                r#"["First."]"#,
+                now + Duration::from_secs(10),
+            );
+
+            assert!(quote_file.refresh(false).unwrap());
+
+            assert_eq!(texts(&quote_file), vec!["First."]);
+
+            rewrite(
+                path.as_path(),
+                r#"["", "Second."]"#,
+                now + Duration::from_secs(20),
+            );
+
+            assert!(quote_file.refresh(false).is_err());
+
+            assert_eq!(texts(&quote_file), vec!["First."]);
+
+            // The invalid file is not read again, until it is changed.
+            assert!(!quote_file.refresh(false).unwrap());
+
+            let _ = remove_file(path.as_path());
+
+            assert!(quote_file.refresh(false).is_err());
+
+            assert_eq!(texts(&quote_file), vec!["First."]);
+
+            return ();
+        }
+
+        #[test]
+        fn changed_modification_time_reloads_file() -> () {
+            let path = quote_file_path("txt");
+
+            let now = SystemTime::now();
+
+            rewrite(path.as_path(), "First.", now);
+
+            let quote_file = QuoteFile::open(path.clone(), SelectionStrategy::Uniform);
+
+            assert!(!quote_file.refresh(false).unwrap());
+
+            rewrite(path.as_path(), "Second.", now);
+
+            // The content is not read, while the modification time is the same, but the forced refresh reads it.
+            assert!(!quote_file.refresh(false).unwrap());
+
+            assert_eq!(texts(&quote_file), vec!["First."]);
+
+            assert!(quote_file.refresh(true).unwrap());
+
+            assert_eq!(texts(&quote_file), vec!["Second."]);
+
+            rewrite(path.as_path(), "Third.", now + Duration::from_secs(10));
+
+            assert!(quote_file.refresh(false).unwrap());
+
+            let _ = remove_file(path.as_path());
+
+            assert_eq!(texts(&quote_file), vec!["Third."]);
+
+            return ();
+        }
+
+        #[test]
+        fn deck_is_not_touched_by_filter_without_match() -> () {
+            let quote_list = QuoteList::built_in(SelectionStrategy::Deck);
+
+            let filter = QuoteFilter::default();
+
+            let mut indices: HashSet<usize> = HashSet::new();
+
+            for _ in 0..(quote_list.len() / 2) {
+                indices.insert(quote_list.draw(CLIENT, &filter).unwrap());
+            }
+
+            let filter_ = QuoteFilter {
+                language: Some("xx".to_string()),
+                ..QuoteFilter::default()
+            };
+
+            assert_eq!(quote_list.draw(CLIENT, &filter_), None);
+
+            // The rest of the deck is drawn without the repetition.
+            for _ in (quote_list.len() / 2)..quote_list.len() {
+                assert!(indices.insert(quote_list.draw(CLIENT, &filter).unwrap()));
+            }
+
+            return ();
+        }
+
+        #[test]
+        fn least_recently_used_deck_is_forgotten() -> () {
+            let mut decks = Decks::default();
+
+            let client = |index: u32| IpAddr::V4(Ipv4Addr::from(index));
+
+            for index in 0..(QuoteList::MAXIMUM_DECKS_QUANTITY as u32) {
+                decks.use_deck(client(index), 1);
+            }
+
+            decks.use_deck(client(0), 1);
+
+            decks.use_deck(client(u32::MAX), 1);
+
+            assert_eq!(decks.decks.len(), QuoteList::MAXIMUM_DECKS_QUANTITY);
+
+            assert_eq!(decks.uses.len(), QuoteList::MAXIMUM_DECKS_QUANTITY);
+
+            assert!(decks.decks.contains_key(&client(0)));
+
+            assert!(!decks.decks.contains_key(&client(1)));
+
+            return ();
+        }
+    }
+}
+
+mod verification_batcher {
+    use infrastructure::tokio::spawn;
+    use infrastructure::tokio::sync::mpsc::channel;
+    use infrastructure::tokio::sync::mpsc::Receiver;
+    use infrastructure::tokio::sync::mpsc::Sender;
+    use infrastructure::tokio::sync::oneshot;
+    use infrastructure::tokio::sync::OwnedSemaphorePermit;
+    use infrastructure::tokio::sync::Semaphore;
+    use infrastructure::tracing::error;
+    use infrastructure::BatchVerifier;
+    use infrastructure::Challenge;
+    use infrastructure::ErrorKind;
+    use infrastructure::ProofPayload;
+    use std::error::Error;
+    use std::sync::Arc;
+
+    pub struct VerificationBatcherConfig {
+        pub batch_size: usize,
+        pub threads_quantity: usize,
+    }
+
+    // Collects the pending verifications of the redemptions into the batches. The batch is not waited for:
+    // it consists of the verifications, that are already pending, so, under the low load every verification
+    // is the batch of its own, and under the high load the batches grow up to the batch size.
+    // The batches are verified by the worker threads of the BatchVerifier, not on the executor, and the batch takes
+    // the permit for every worker it is split between, so, the verifications, that wait for the permits,
+    // form the next batch instead of the queue of the small batches.
+    pub struct VerificationBatcher {
+        sender: Sender<PendingVerification>,
+    }
+
+    struct PendingVerification {
+        challenge: Challenge,
+        proof_payload: ProofPayload,
+        sender: oneshot::Sender<bool>,
+    }
+
+    impl VerificationBatcher {
+        pub fn new(config: VerificationBatcherConfig) -> Result<Self, Box<dyn Error + 'static>> {
+            let batch_verifier = Arc::new(BatchVerifier::new(config.threads_quantity)?);
+
+            let (sender, receiver) = channel(config.batch_size.saturating_mul(64));
+
+            spawn(Self::run(config, batch_verifier, receiver));
+
+            return Ok(Self { sender });
+        }
+
+        pub async fn verify<'a>(
+            &'a self,
+            challenge: Challenge,
+            proof_payload: ProofPayload,
+        ) -> Result<bool, Box<dyn Error + 'static>> {
+            let (sender, receiver) = oneshot::channel();
+
+            let pending_verification = PendingVerification {
+                challenge,
+                proof_payload,
+                sender,
+            };
+
+            if self.sender.send(pending_verification).await.is_err() {
+                return Err(Box::from(ErrorKind::RunTime));
+            }
+
+            return match receiver.await {
+                Ok(result) => Ok(result),
+                Err(error) => Err(Box::from(error)),
+            };
+        }
+
+        async fn run(
+            config: VerificationBatcherConfig,
+            batch_verifier: Arc<BatchVerifier>,
+            mut receiver: Receiver<PendingVerification>,
+        ) -> () {
+            let semaphore = Arc::new(Semaphore::new(config.threads_quantity));
+
+            'a: loop {
+                let mut batch = match receiver.recv().await {
+                    Some(pending_verification) => vec![pending_verification],
+                    None => {
+                        break 'a;
+                    }
+                };
+
+                'b: while batch.len() < config.batch_size {
+                    match receiver.try_recv() {
+                        Ok(pending_verification) => {
+                            batch.push(pending_verification);
+                        }
+                        Err(_) => {
+                            break 'b;
+                        }
+                    }
+                }
+
+                let permits_quantity = batch.len().min(config.threads_quantity) as u32;
+
+                let permit = match semaphore.clone().acquire_many_owned(permits_quantity).await {
+                    Ok(permit_) => permit_,
+                    Err(error) => {
+                        error!("{}", &error);
+
+                        break 'a;
+                    }
+                };
+
+                spawn(Self::verify_batch(batch_verifier.clone(), batch, permit));
+            }
+
+            return ();
+        }
+
+        async fn verify_batch(
+            batch_verifier: Arc<BatchVerifier>,
+            batch: Vec<PendingVerification>,
+            permit: OwnedSemaphorePermit,
+        ) -> () {
+            let (verifications, senders): (
+                Vec<(Challenge, ProofPayload)>,
+                Vec<oneshot::Sender<bool>>,
+            ) = batch
+                .into_iter()
+                .map(|pending_verification| {
+                    (
+                        (
+                            pending_verification.challenge,
+                            pending_verification.proof_payload,
+                        ),
+                        pending_verification.sender,
+                    )
+                })
+                .unzip();
+
+            let results = batch_verifier.verify(verifications).await;
+
+            // The threads are released only after the verification.
+            drop(permit);
+
+            // The redemption, that is not waited for anymore, does not need the result.
+            for (sender, result) in senders.into_iter().zip(results) {
+                let _ = sender.send(result);
+            }
+
+            return ();
+        }
+    }
+}
+
+mod metrics {
+    use super::difficulty_controller::Decision;
+    use infrastructure::tokio::time::interval;
+    use infrastructure::tracing::info;
+    use infrastructure::Difficulty;
+    use infrastructure::Timeout;
+    use std::fmt::Display;
+    use std::fmt::Formatter;
+    use std::fmt::Result as FmtResult;
+    use std::sync::atomic::AtomicU32;
+    use std::sync::atomic::AtomicU64;
+    use std::sync::atomic::AtomicU8;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    const DIFFICULTIES_QUANTITY: usize = (Difficulty::MAXIMUM.zero_bits() as usize) + 1;
+
+    pub struct Metrics {
+        issued_challenges: AtomicU64,
+        raised_difficulty_challenges: AtomicU64,
+        challenges_per_difficulty: [AtomicU64; DIFFICULTIES_QUANTITY],
+        last_difficulty: AtomicU8,
+        last_pressure: AtomicU32,
+        last_outstanding_challenges_quantity: AtomicUsize,
+        successful_redemptions: AtomicU64,
+        failed_redemptions: AtomicU64,
+        header_read_timeouts: AtomicU64,
+        body_read_timeouts: AtomicU64,
+        request_timeouts: AtomicU64,
+        response_write_timeouts: AtomicU64,
+    }
+
+    impl Metrics {
+        pub fn new() -> Self {
+            return Self {
+                issued_challenges: AtomicU64::new(0),
+                raised_difficulty_challenges: AtomicU64::new(0),
+                challenges_per_difficulty: std::array::from_fn(|_| AtomicU64::new(0)),
+                last_difficulty: AtomicU8::new(0),
+                last_pressure: AtomicU32::new(0),
+                last_outstanding_challenges_quantity: AtomicUsize::new(0),
+                successful_redemptions: AtomicU64::new(0),
+                failed_redemptions: AtomicU64::new(0),
+                header_read_timeouts: AtomicU64::new(0),
+                body_read_timeouts: AtomicU64::new(0),
+                request_timeouts: AtomicU64::new(0),
+                response_write_timeouts: AtomicU64::new(0),
+            };
+        }
+
+        pub fn record_difficulty_decision<'a>(&'a self, decision: &'a Decision) -> () {
+            let zero_bits = decision.difficulty.zero_bits();
+
+            self.issued_challenges.fetch_add(1, Ordering::Relaxed);
+
+            if decision.pressure > 0 {
+                self.raised_difficulty_challenges
+                    .fetch_add(1, Ordering::Relaxed);
+            }
+
+            self.challenges_per_difficulty[zero_bits as usize].fetch_add(1, Ordering::Relaxed);
+
+            self.last_difficulty.store(zero_bits, Ordering::Relaxed);
+
+            self.last_pressure
+                .store(decision.pressure, Ordering::Relaxed);
+
+            self.last_outstanding_challenges_quantity
+                .store(decision.outstanding_challenges_quantity, Ordering::Relaxed);
+
+            return ();
+        }
+
+        pub fn record_redemption<'a>(&'a self, is_successful: bool) -> () {
+            if is_successful {
+                self.successful_redemptions.fetch_add(1, Ordering::Relaxed);
+            } else {
+                self.failed_redemptions.fetch_add(1, Ordering::Relaxed);
+            }
+
+            return ();
+        }
+
+        pub fn record_timeout(&self, timeout: Timeout) -> () {
+            let timeouts = match timeout {
+                Timeout::HeaderRead => &self.header_read_timeouts,
+                Timeout::BodyRead => &self.body_read_timeouts,
+                Timeout::Request => &self.request_timeouts,
+                Timeout::ResponseWrite => &self.response_write_timeouts,
+            };
+
+            timeouts.fetch_add(1, Ordering::Relaxed);
+
+            return ();
+        }
+
+        pub async fn report(metrics: Arc<Self>, period: Duration) -> () {
+            let mut interval_ = interval(period);
+
+            interval_.tick().await;
+
+            loop {
+                interval_.tick().await;
+
+                info!("{}", metrics.as_ref());
+            }
+        }
+    }
+
+    impl Display for Metrics {
+        fn fmt<'a>(&'a self, formatter: &'a mut Formatter<'_>) -> FmtResult {
+            write!(
+                formatter,
+                "Metrics: issued challenges {}, raised difficulty challenges {}, last difficulty {} zero bits, last pressure {}, last outstanding challenges {}, successful redemptions {}, failed redemptions {}, timeouts (header read {}, body read {}, request {}, response write {}), challenges per difficulty [",
+                self.issued_challenges.load(Ordering::Relaxed),
+                self.raised_difficulty_challenges.load(Ordering::Relaxed),
+                self.last_difficulty.load(Ordering::Relaxed),
+                self.last_pressure.load(Ordering::Relaxed),
+                self.last_outstanding_challenges_quantity.load(Ordering::Relaxed),
+                self.successful_redemptions.load(Ordering::Relaxed),
+                self.failed_redemptions.load(Ordering::Relaxed),
+                self.header_read_timeouts.load(Ordering::Relaxed),
+                self.body_read_timeouts.load(Ordering::Relaxed),
+                self.request_timeouts.load(Ordering::Relaxed),
+                self.response_write_timeouts.load(Ordering::Relaxed),
+            )?;
+
+            let mut is_first = true;
+
+            for (zero_bits, challenges) in self.challenges_per_difficulty.iter().enumerate() {
+                let challenges_ = challenges.load(Ordering::Relaxed);
+
+                if challenges_ == 0 {
+                    continue;
+                }
+
+                if !is_first {
+                    write!(formatter, ", ")?;
+                }
+
+                write!(formatter, "{}: {}", zero_bits, challenges_)?;
+
+                is_first = false;
+            }
+
+            write!(formatter, "].")
+        }
+    }
+}
+
+mod proxy {
+    use infrastructure::tokio::io::copy_bidirectional;
+    use infrastructure::tokio::net::TcpStream;
+    use infrastructure::tracing::debug;
+    use infrastructure::tracing::info;
+    use infrastructure::tracing::Span;
+    use infrastructure::uuid::Uuid;
+    use infrastructure::Admission;
+    use std::collections::HashMap;
+    use std::error::Error;
+    use std::sync::Mutex;
+    use std::sync::MutexGuard;
+    use std::sync::PoisonError;
+    use std::time::Duration;
+    use std::time::Instant;
+
+    pub struct ProxyConfig {
+        pub upstream_address: String,
+        pub ticket_lifetime: Duration,
+    }
+
+    // The gate of the upstream: the admitted connection is spliced to the upstream.
+    pub struct Proxy {
+        config: ProxyConfig,
+        // The expiration of the ticket, that is not presented yet.
+        tickets: Mutex<HashMap<Uuid, Instant>>,
+    }
+
+    impl Proxy {
+        pub fn new(config: ProxyConfig) -> Self {
+            return Self {
+                config,
+                tickets: Mutex::new(HashMap::new()),
+            };
+        }
+
+        pub fn ticket_lifetime<'a>(&'a self) -> Duration {
+            return self.config.ticket_lifetime;
+        }
+
+        // The ticket is issued for the redeemed challenge only, so, the expired tickets
+        // are forgotten here and the quantity of the kept tickets is bounded by the work of the clients.
+        pub fn issue_ticket<'a>(&'a self) -> Uuid {
+            let now = Instant::now();
+
+            let ticket = Uuid::new_v4();
+
+            let mut tickets = self.lock_tickets();
+
+            tickets.retain(|_, expiration| *expiration > now);
+
+            tickets.insert(ticket, now + self.config.ticket_lifetime);
+
+            return ticket;
+        }
+
+        // The ticket is presented once.
+        pub fn redeem_ticket<'a>(&'a self, ticket: &'a Uuid) -> bool {
+            return match self.lock_tickets().remove(ticket) {
+                Some(expiration) => expiration > Instant::now(),
+                None => false,
+            };
+        }
+
+        // The admission is the outcome of the request.
+        pub fn record_admission<'a>(&'a self, admission: &'a Admission) -> () {
+            let outcome = match *admission {
+                Admission::Admitted => "admitted",
+                Admission::Ticket { ref ticket, .. } => {
+                    debug!("The ticket {} is issued.", ticket);
+
+                    "ticket"
+                }
+                Admission::Fail => "refused",
+            };
+
+            Span::current().record("outcome", outcome);
+
+            return ();
+        }
+
+        pub async fn splice<'a>(
+            &'a self,
+            tcp_stream: &'a mut TcpStream,
+        ) -> Result<(), Box<dyn Error + 'static>> {
+            let mut upstream_tcp_stream =
+                match TcpStream::connect(self.config.upstream_address.as_str()).await {
+                    Ok(upstream_tcp_stream_) => upstream_tcp_stream_,
+                    Err(error) => {
+                        return Err(Box::from(error));
+                    }
+                };
+
+            let (sent_bytes_quantity, received_bytes_quantity) =
+                match copy_bidirectional(tcp_stream, &mut upstream_tcp_stream).await {
+                    Ok(bytes_quantities) => bytes_quantities,
+                    Err(error) => {
+                        return Err(Box::from(error));
+                    }
+                };
+
+            info!(
+                "The connection is spliced to {}: {} bytes are sent, {} bytes are received.",
+                self.config.upstream_address.as_str(),
+                sent_bytes_quantity,
+                received_bytes_quantity,
+            );
+
+            return Ok(());
+        }
+
+        fn lock_tickets<'a>(&'a self) -> MutexGuard<'a, HashMap<Uuid, Instant>> {
+            return self.tickets.lock().unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::Proxy;
+        use super::ProxyConfig;
+        use infrastructure::tokio::io::AsyncReadExt;
+        use infrastructure::tokio::io::AsyncWriteExt;
+        use infrastructure::tokio::join;
+        use infrastructure::tokio::net::TcpListener;
+        use infrastructure::tokio::net::TcpStream;
+        use infrastructure::uuid::Uuid;
+        use std::thread::sleep;
+        use std::time::Duration;
+
+        fn proxy(upstream_address: String, ticket_lifetime: Duration) -> Proxy {
+            return Proxy::new(ProxyConfig {
+                upstream_address,
+                ticket_lifetime,
+            });
+        }
+
+        #[test]
+        fn ticket_is_redeemed_once() -> () {
+            let proxy_ = proxy(String::from("127.0.0.1:0"), Duration::from_secs(60));
+
+            let ticket = proxy_.issue_ticket();
+
+            assert!(proxy_.redeem_ticket(&ticket));
+
+            assert!(!proxy_.redeem_ticket(&ticket));
+
+            assert!(!proxy_.redeem_ticket(&Uuid::new_v4()));
+
+            return ();
+        }
+
+        #[test]
+        fn expired_ticket_is_refused() -> () {
+            let proxy_ = proxy(String::from("127.0.0.1:0"), Duration::from_millis(10));
+
+            let ticket = proxy_.issue_ticket();
+
+            let ticket_ = proxy_.issue_ticket();
+
+            sleep(Duration::from_millis(20));
+
+            assert!(!proxy_.redeem_ticket(&ticket));
+
+            // The expired tickets are forgotten by the issue of the next one.
+            proxy_.issue_ticket();
+
+            assert_eq!(proxy_.lock_tickets().len(), 1);
+
+            assert!(!proxy_.redeem_ticket(&ticket_));
+
+            return ();
+        }
+
+        #[infrastructure::tokio::test(crate = "infrastructure::tokio")]
+        async fn spliced_connection_reaches_upstream() -> () {
+            let upstream_tcp_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+
+            let proxy_ = proxy(
+                upstream_tcp_listener.local_addr().unwrap().to_string(),
+                Duration::from_secs(60),
+            );
+
+            let tcp_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+
+            let (client_tcp_stream, accepted) = join!(
+                TcpStream::connect(tcp_listener.local_addr().unwrap()),
+                tcp_listener.accept()
+            );
+
+            let mut client_tcp_stream_ = client_tcp_stream.unwrap();
+
+            let (mut tcp_stream, _) = accepted.unwrap();
+
+            let (spliced, (), ()) = join!(
+                proxy_.splice(&mut tcp_stream),
+                // The upstream answers the request of the client, that is passed through the proxy.
+                async {
+                    let (mut upstream_tcp_stream, _) =
+                        upstream_tcp_listener.accept().await.unwrap();
+
+                    let mut request: Vec<u8> = vec![];
+
+                    upstream_tcp_stream.read_to_end(&mut request).await.unwrap();
+
+                    assert_eq!(request, b"request");
+
+                    upstream_tcp_stream.write_all(b"response").await.unwrap();
+                },
+                async {
+                    client_tcp_stream_.write_all(b"request").await.unwrap();
+
+                    client_tcp_stream_.shutdown().await.unwrap();
+
+                    let mut response: Vec<u8> = vec![];
+
+                    client_tcp_stream_.read_to_end(&mut response).await.unwrap();
+
+                    assert_eq!(response, b"response");
+                }
+            );
+
+            assert!(spliced.is_ok());
+
+            return ();
+        }
+    }
+}
+
+mod shutdown {
+    use infrastructure::tokio::select;
+    use infrastructure::tokio::signal::ctrl_c;
+    use infrastructure::tokio::signal::unix::signal;
+    use infrastructure::tokio::signal::unix::SignalKind;
+    use infrastructure::tracing::error;
+    use infrastructure::uuid::Uuid;
+    use infrastructure::Algorithm;
+    use infrastructure::Challenge;
+    use infrastructure::ChallengeTable;
+    use infrastructure::IssuedChallenge;
+    use infrastructure::Serializer;
+    use std::error::Error;
+    use std::fmt::Display;
+    use std::fmt::Formatter;
+    use std::fmt::Result as FmtResult;
+    use std::fs::read;
+    use std::fs::rename;
+    use std::fs::write;
+    use std::io::ErrorKind as IoErrorKind;
+    use std::path::Path;
+    use std::time::Duration;
+    use std::time::SystemTime;
+    use std::time::UNIX_EPOCH;
+
+    pub struct Shutdown {
+        // The connections, that are in progress, when the shutdown is requested.
+        pub connections_quantity: usize,
+        // The connections, that are not finished within the drain timeout.
+        pub aborted_connections_quantity: usize,
+        // Is None, if the challenges are not persistent.
+        pub stored_challenges_quantity: Option<usize>,
+    }
+
+    impl Shutdown {
+        pub fn is_complete<'a>(&'a self) -> bool {
+            return self.aborted_connections_quantity == 0;
+        }
+    }
+
+    impl Display for Shutdown {
+        fn fmt<'a>(&'a self, formatter: &'a mut Formatter<'_>) -> FmtResult {
+            write!(
+                formatter,
+                "The server is stopped: {} of {} connections are drained, {} are aborted",
+                self.connections_quantity - self.aborted_connections_quantity,
+                self.connections_quantity,
+                self.aborted_connections_quantity,
+            )?;
+
+            if let Some(stored_challenges_quantity) = self.stored_challenges_quantity {
+                write!(
+                    formatter,
+                    ", {} challenges are stored",
+                    stored_challenges_quantity
+                )?;
+            }
+
+            write!(formatter, ".")
+        }
+    }
+
+    // Is completed on the SIGINT or on the SIGTERM.
+    pub async fn wait_for_termination() -> () {
+        let mut terminate = match signal(SignalKind::terminate()) {
+            Ok(terminate_) => terminate_,
+            Err(error) => {
+                error!("{}", &error);
+
+                if let Err(error_) = ctrl_c().await {
+                    error!("{}", &error_);
+                }
+
+                return ();
+            }
+        };
+
+        select! {
+            _ = ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+
+        return ();
+    }
+
+    // The absent file is the empty one. The expired challenges are not loaded, and the challenge does not live longer,
+    // than the lifetime, even if it is shortened meanwhile. The time-lock challenges are skipped as on the storing.
+    pub fn load_challenges<'a>(
+        path: &'a Path,
+        challenge_table: &'a ChallengeTable,
+    ) -> Result<usize, Box<dyn Error + 'static>> {
+        let data = match read(path) {
+            Ok(data_) => data_,
+            Err(error) => {
+                if error.kind() == IoErrorKind::NotFound {
+                    return Ok(0);
+                }
+
+                return Err(Box::from(error));
+            }
+        };
+
+        let challenges =
+            Serializer::deserialize::<'_, Vec<(Uuid, Challenge, u64)>>(data.as_slice())?;
+
+        let now = SystemTime::now();
+
+        let mut challenges_quantity: usize = 0;
+
+        for (token, challenge, expiration) in challenges.into_iter() {
+            if challenge.algorithm == Algorithm::TimeLock {
+                continue;
+            }
+
+            let issued_challenge = IssuedChallenge {
+                challenge,
+                expiration: (UNIX_EPOCH + Duration::from_secs(expiration))
+                    .min(now + challenge_table.lifetime()),
+                client: None,
+            };
+
+            if issued_challenge.is_expired_at(now) {
+                continue;
+            }
+
+            challenge_table.restore(token, issued_challenge);
+
+            challenges_quantity += 1;
+        }
+
+        return Ok(challenges_quantity);
+    }
+
+    // The file is replaced at once, so, the interrupted storing does not corrupt it. Every challenge is stored
+    // with its expiration in seconds since the unix epoch. The time-lock challenges are not stored, because
+    // the trapdoor, that makes their check cheap, is not serialized, and without it every check costs the server
+    // as much, as the solution costs the client.
+    pub fn store_challenges<'a>(
+        path: &'a Path,
+        challenge_table: &'a ChallengeTable,
+    ) -> Result<usize, Box<dyn Error + 'static>> {
+        let challenges: Vec<(Uuid, Challenge, u64)> = challenge_table
+            .snapshot()
+            .into_iter()
+            .filter(|(_, issued_challenge)| {
+                return issued_challenge.challenge.algorithm != Algorithm::TimeLock;
+            })
+            .map(|(token, issued_challenge)| {
+                let expiration = issued_challenge
+                    .expiration
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |duration| duration.as_secs());
+
+                return (token, issued_challenge.challenge, expiration);
+            })
+            .collect();
+
+        let data = Serializer::serialize(&challenges)?;
+
+        let mut temporary_path = path.as_os_str().to_owned();
+
+        temporary_path.push(".tmp");
+
+        if let Err(error) = write(temporary_path.as_os_str(), data) {
+            return Err(Box::from(error));
+        }
+
+        if let Err(error) = rename(temporary_path.as_os_str(), path) {
+            return Err(Box::from(error));
+        }
+
+        return Ok(challenges.len());
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::load_challenges;
+        use super::store_challenges;
+        use infrastructure::uuid::Uuid;
+        use infrastructure::Algorithm;
+        use infrastructure::Challenge;
+        use infrastructure::ChallengeTable;
+        use infrastructure::Difficulty;
+        use infrastructure::Serializer;
+        use infrastructure::POW;
+        use std::env::temp_dir;
+        use std::fs::read;
+        use std::fs::remove_file;
+        use std::fs::write;
+        use std::time::Duration;
+        use std::time::SystemTime;
+        use std::time::UNIX_EPOCH;
+
+        fn challenge_table() -> ChallengeTable {
+            return ChallengeTable::new(
+                1,
+                ChallengeTable::DEFAULT_LIFETIME,
+                ChallengeTable::DEFAULT_MAXIMUM_CLIENT_CHALLENGES_QUANTITY,
+            );
+        }
+
+        fn challenge(algorithm: Algorithm) -> Challenge {
+            let mut challenge = POW::new(Algorithm::DoubleSha256)
+                .unwrap()
+                .generate_challenge(Difficulty::I);
+
+            challenge.algorithm = algorithm;
+
+            return challenge;
+        }
+
+        #[test]
+        fn time_lock_challenge_is_not_stored() -> () {
+            let path = temp_dir().join(format!("challenges_{}", Uuid::new_v4()));
+
+            let challenge_table = challenge_table();
+
+            let token = Uuid::new_v4();
+
+            challenge_table.insert(token, challenge(Algorithm::DoubleSha256));
+
+            challenge_table.insert(Uuid::new_v4(), challenge(Algorithm::TimeLock));
+
+            assert_eq!(
+                store_challenges(path.as_path(), &challenge_table).unwrap(),
+                1
+            );
+
+            let challenges = Serializer::deserialize::<'_, Vec<(Uuid, Challenge, u64)>>(
+                read(path.as_path()).unwrap().as_slice(),
+            )
+            .unwrap();
+
+            let _ = remove_file(path.as_path());
+
+            assert_eq!(challenges.len(), 1);
+
+            assert_eq!(challenges[0].0, token);
+
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+
+            assert!(challenges[0].2 > now.as_secs());
+
+            assert!(challenges[0].2 <= (now + ChallengeTable::DEFAULT_LIFETIME).as_secs());
+
+            return ();
+        }
+
+        #[test]
+        fn expired_and_time_lock_challenges_are_not_loaded() -> () {
+            let path = temp_dir().join(format!("challenges_{}", Uuid::new_v4()));
+
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+
+            let token = Uuid::new_v4();
+
+            let expired_token = Uuid::new_v4();
+
+            let time_lock_token = Uuid::new_v4();
+
+            let lasting_token = Uuid::new_v4();
+
+            let challenges: Vec<(Uuid, Challenge, u64)> = vec![
+                (
+                    token,
+                    challenge(Algorithm::DoubleSha256),
+                    (now + Duration::from_secs(60)).as_secs(),
+                ),
+                (
+                    expired_token,
+                    challenge(Algorithm::DoubleSha256),
+                    (now - Duration::from_secs(1)).as_secs(),
+                ),
+                (
+                    time_lock_token,
+                    challenge(Algorithm::TimeLock),
+                    (now + Duration::from_secs(60)).as_secs(),
+                ),
+                // Outlives the lifetime of the table.
+                (
+                    lasting_token,
+                    challenge(Algorithm::DoubleSha256),
+                    (now + ChallengeTable::DEFAULT_LIFETIME * 10).as_secs(),
+                ),
+            ];
+
+            write(path.as_path(), Serializer::serialize(&challenges).unwrap()).unwrap();
+
+            let challenge_table = challenge_table();
+
+            let challenges_quantity = load_challenges(path.as_path(), &challenge_table).unwrap();
+
+            let _ = remove_file(path.as_path());
+
+            assert_eq!(challenges_quantity, 2);
+
+            assert!(challenge_table.take(&expired_token).is_none());
+
+            assert!(challenge_table.take(&time_lock_token).is_none());
+
+            let issued_challenge = challenge_table.take(&token).unwrap();
+
+            assert!(issued_challenge.expiration <= UNIX_EPOCH + now + Duration::from_secs(60));
+
+            let issued_challenge_ = challenge_table.take(&lasting_token).unwrap();
+
+            assert!(
+                issued_challenge_.expiration
+                    <= SystemTime::now() + ChallengeTable::DEFAULT_LIFETIME
+            );
+
+            return ();
+        }
+
+        #[test]
+        fn absent_file_is_empty() -> () {
+            let path = temp_dir().join(format!("challenges_{}", Uuid::new_v4()));
+
+            assert_eq!(
+                load_challenges(path.as_path(), &challenge_table()).unwrap(),
+                0
+            );
+
+            return ();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::config::Config;
+    use super::config::Deadlines;
+    use super::difficulty_controller::DifficultyController;
+    use super::handle_request;
+    use super::handle_stream;
+    use super::metrics::Metrics;
+    use super::negotiate_algorithm;
+    use super::respond;
+    use super::run_tcp_server;
+    use super::verification_batcher::VerificationBatcher;
+    use super::Environment;
+    use infrastructure::tokio::io::AsyncWriteExt;
+    use infrastructure::tokio::join;
+    use infrastructure::tokio::net::TcpListener;
+    use infrastructure::tokio::net::TcpSocket;
+    use infrastructure::tokio::net::TcpStream;
+    use infrastructure::tokio::sync::oneshot;
+    use infrastructure::tokio::time::sleep;
+    use infrastructure::uuid::Uuid;
+    use infrastructure::Algorithm;
+    use infrastructure::Challenge;
+    use infrastructure::ChallengeTable;
+    use infrastructure::Difficulty;
+    use infrastructure::Proof;
+    use infrastructure::ProofPayload;
+    use infrastructure::Protocol;
+    use infrastructure::QuoteFilter;
+    use infrastructure::Request;
+    use infrastructure::Response;
+    use infrastructure::Serializer;
+    use infrastructure::Timeout;
+    use infrastructure::WordOfWisdom;
+    use infrastructure::POW;
+    use std::borrow::Cow;
+    use std::env::temp_dir;
+    use std::error::Error;
+    use std::fs::read;
+    use std::fs::remove_file;
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use std::time::Instant;
+
+    // The lowest difficulty, so, the challenges are solved quickly.
+    fn config() -> Config {
+        let mut config = Config::from_environment().unwrap();
+
+        config.difficulty_controller.minimum = Difficulty::I;
+
+        config.difficulty_controller.baseline = Difficulty::I;
+
+        return config;
+    }
+
+    const SHORT_DEADLINE: Duration = Duration::from_millis(100);
+
+    const LONG_DEADLINE: Duration = Duration::from_secs(5);
+
+    // The environment of the handlers, as the server builds it.
+    fn environment(deadlines: Deadlines) -> Environment {
+        let config = config();
+
+        return Environment {
+            challenge_table: Arc::new(ChallengeTable::new(
+                ChallengeTable::DEFAULT_SHARDS_QUANTITY,
+                config.challenge_lifetime,
+                config.maximum_client_challenges_quantity,
+            )),
+            proof_of_work: Arc::new(config.proof_of_work),
+            difficulty_controller: Arc::new(DifficultyController::new(
+                config.difficulty_controller,
+            )),
+            verification_batcher: Arc::new(
+                VerificationBatcher::new(config.verification_batcher).unwrap(),
+            ),
+            protected_resource: config.protected_resource,
+            proxy: None,
+            access_token_issuer: None,
+            deadlines,
+            metrics: Arc::new(Metrics::new()),
+        };
+    }
+
+    // The accepted stream of the server and the stream of the client. The buffers are small,
+    // so, the client, that does not read, stalls the writing of the server soon.
+    async fn connect() -> (TcpStream, SocketAddr, TcpStream) {
+        let tcp_socket = TcpSocket::new_v4().unwrap();
+
+        tcp_socket.set_send_buffer_size(4096).unwrap();
+
+        tcp_socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+
+        let tcp_listener = tcp_socket.listen(1).unwrap();
+
+        let client_tcp_socket = TcpSocket::new_v4().unwrap();
+
+        client_tcp_socket.set_recv_buffer_size(4096).unwrap();
+
+        let (client_tcp_stream, accepted) = join!(
+            client_tcp_socket.connect(tcp_listener.local_addr().unwrap()),
+            tcp_listener.accept()
+        );
+
+        let (tcp_stream, socket_address) = accepted.unwrap();
+
+        return (tcp_stream, socket_address, client_tcp_stream.unwrap());
+    }
+
+    fn timeout_of<'a>(error: &'a (dyn Error + 'static)) -> Option<Timeout> {
+        return error.downcast_ref::<Timeout>().copied();
+    }
+
+    // The counters of the header read, body read, request and response write timeouts.
+    fn has_timeouts<'a>(environment: &'a Environment, timeouts: [u64; 4]) -> bool {
+        return environment.metrics.to_string().contains(
+            format!(
+                "timeouts (header read {}, body read {}, request {}, response write {})",
+                timeouts[0], timeouts[1], timeouts[2], timeouts[3]
+            )
+            .as_str(),
+        );
+    }
+
+    async fn exchange<'a>(socket_address: SocketAddr, request: &'a Request<'a>) -> Vec<u8> {
+        let mut tcp_stream = TcpStream::connect(socket_address).await.unwrap();
+
+        Protocol::send(&mut tcp_stream, Serializer::serialize(request).unwrap())
+            .await
+            .unwrap();
+
+        return Protocol::receive(&mut tcp_stream).await.unwrap();
+    }
+
+    async fn request_challenge<'a>(socket_address: SocketAddr, token: &'a Uuid) -> Challenge {
+        let data = exchange(
+            socket_address,
+            &Request::Challenge {
+                token: Cow::Borrowed(token),
+                algorithms: vec![Algorithm::DoubleSha256],
+            },
+        )
+        .await;
+
+        return match Serializer::deserialize::<'_, Response>(data.as_slice()).unwrap() {
+            Response::Challenge { challenge } => challenge,
+            _ => panic!("The challenge is not issued."),
+        };
+    }
+
+    async fn redeem<'a>(socket_address: SocketAddr, token: &'a Uuid, proof: ProofPayload) -> bool {
+        let data = exchange(
+            socket_address,
+            &Request::WordOfWisdom {
+                token: Cow::Borrowed(token),
+                proof,
+                filter: QuoteFilter::default(),
+            },
+        )
+        .await;
+
+        return match Serializer::deserialize::<'_, Response>(data.as_slice()).unwrap() {
+            Response::WordOfWisdom {
+                word_of_wisdom: WordOfWisdom::Result { .. },
+            } => true,
+            Response::WordOfWisdom {
+                word_of_wisdom: WordOfWisdom::Fail,
+            } => false,
+            _ => panic!("The word of wisdom is not answered."),
+        };
+    }
+
+    #[infrastructure::tokio::test(crate = "infrastructure::tokio")]
+    async fn challenge_is_spent_by_failed_proof() -> () {
+        let tcp_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+
+        let socket_address = tcp_listener.local_addr().unwrap();
+
+        let (sender, receiver) = oneshot::channel::<()>();
+
+        let (shutdown, ()) = join!(
+            run_tcp_server(config(), tcp_listener, async move {
+                let _ = receiver.await;
+            }),
+            async move {
+                let token = Uuid::new_v4();
+
+                let challenge = request_challenge(socket_address, &token).await;
+
+                let mut p_o_w = POW::new(challenge.algorithm).unwrap();
+
+                let proof = p_o_w.solve(&challenge).unwrap();
+
+                let mut nonce: u64 = 0;
+
+                let wrong_proof = 'a: loop {
+                    let proof_ = ProofPayload::encode(
+                        challenge.algorithm,
+                        &Proof::Nonce(nonce.to_be_bytes().to_vec()),
+                    )
+                    .unwrap();
+
+                    if !p_o_w.verify(&challenge, &proof_).unwrap() {
+                        break 'a proof_;
+                    }
+
+                    nonce += 1;
+                };
+
+                assert!(!redeem(socket_address, &token, wrong_proof).await);
+
+                // The right proof of the spent challenge is not checked.
+                assert!(!redeem(socket_address, &token, proof).await);
+
+                let token_ = Uuid::new_v4();
+
+                let challenge_ = request_challenge(socket_address, &token_).await;
+
+                let proof_ = p_o_w.solve(&challenge_).unwrap();
+
+                assert!(redeem(socket_address, &token_, proof_).await);
+
+                let _ = sender.send(());
+            }
+        );
+
+        assert!(shutdown.unwrap().is_complete());
+
+        return ();
+    }
+
+    #[infrastructure::tokio::test(crate = "infrastructure::tokio")]
+    async fn request_in_progress_is_drained_and_challenges_are_stored() -> () {
+        let challenges_file_path = temp_dir().join(format!("challenges_{}", Uuid::new_v4()));
+
+        let mut config = config();
+
+        config.challenges_file_path = Some(challenges_file_path.clone());
+
+        let tcp_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+
+        let socket_address = tcp_listener.local_addr().unwrap();
+
+        let (sender, receiver) = oneshot::channel::<()>();
+
+        let token = Uuid::new_v4();
+
+        let token_ = Uuid::new_v4();
+
+        let (shutdown, ()) = join!(
+            run_tcp_server(config, tcp_listener, async move {
+                let _ = receiver.await;
+            }),
+            async move {
+                request_challenge(socket_address, &token).await;
+
+                // The connection is accepted, but its request is sent after the shutdown is requested.
+                let mut tcp_stream = TcpStream::connect(socket_address).await.unwrap();
+
+                sleep(Duration::from_millis(100)).await;
+
+                let _ = sender.send(());
+
+                let request = Request::Challenge {
+                    token: Cow::Borrowed(&token_),
+                    algorithms: vec![Algorithm::DoubleSha256],
+                };
+
+                Protocol::send(&mut tcp_stream, Serializer::serialize(&request).unwrap())
+                    .await
+                    .unwrap();
+
+                let data = Protocol::receive(&mut tcp_stream).await.unwrap();
+
+                assert!(matches!(
+                    Serializer::deserialize::<'_, Response>(data.as_slice()).unwrap(),
+                    Response::Challenge { .. }
+                ));
+            }
+        );
+
+        let shutdown_ = shutdown.unwrap();
+
+        assert!(shutdown_.is_complete());
+
+        assert_eq!(shutdown_.connections_quantity, 1);
+
+        assert_eq!(shutdown_.stored_challenges_quantity, Some(2));
+
+        let challenges = Serializer::deserialize::<'_, Vec<(Uuid, Challenge, u64)>>(
+            read(challenges_file_path.as_path()).unwrap().as_slice(),
+        )
+        .unwrap();
+
+        let _ = remove_file(challenges_file_path.as_path());
+
+        let mut tokens: Vec<Uuid> = challenges.iter().map(|(token__, _, _)| *token__).collect();
+
+        tokens.sort();
+
+        let mut expected_tokens = vec![token, token_];
+
+        expected_tokens.sort();
+
+        assert_eq!(tokens, expected_tokens);
+
+        return ();
+    }
+
+    #[infrastructure::tokio::test(crate = "infrastructure::tokio")]
+    async fn silent_client_exceeds_header_read_deadline() -> () {
+        let environment_ = environment(Deadlines {
+            header_read: SHORT_DEADLINE,
+            body_read: LONG_DEADLINE,
+            request: LONG_DEADLINE,
+            response_write: LONG_DEADLINE,
+        });
+
+        let (mut tcp_stream, socket_address, _client_tcp_stream) = connect().await;
+
+        let error = handle_request(&mut tcp_stream, socket_address, &environment_)
+            .await
+            .err()
+            .unwrap();
+
+        assert_eq!(timeout_of(error.as_ref()), Some(Timeout::HeaderRead));
+
+        assert!(has_timeouts(&environment_, [1, 0, 0, 0]));
+
+        return ();
+    }
+
+    #[infrastructure::tokio::test(crate = "infrastructure::tokio")]
+    async fn client_stalled_after_length_exceeds_body_read_deadline() -> () {
+        let environment_ = environment(Deadlines {
+            header_read: LONG_DEADLINE,
+            body_read: SHORT_DEADLINE,
+            request: LONG_DEADLINE,
+            response_write: LONG_DEADLINE,
+        });
+
+        let (mut tcp_stream, socket_address, mut client_tcp_stream) = connect().await;
+
+        // The length of the frame is sent, but its body is not.
+        client_tcp_stream
+            .write_all(&64_u64.to_be_bytes())
+            .await
+            .unwrap();
+
+        let error = handle_request(&mut tcp_stream, socket_address, &environment_)
+            .await
+            .err()
+            .unwrap();
+
+        assert_eq!(timeout_of(error.as_ref()), Some(Timeout::BodyRead));
+
+        assert!(has_timeouts(&environment_, [0, 1, 0, 0]));
+
+        return ();
+    }
+
+    #[infrastructure::tokio::test(crate = "infrastructure::tokio")]
+    async fn slow_request_exceeds_request_deadline() -> () {
+        let environment_ = environment(Deadlines {
+            header_read: LONG_DEADLINE,
+            body_read: LONG_DEADLINE,
+            request: SHORT_DEADLINE,
+            response_write: LONG_DEADLINE,
+        });
+
+        let (tcp_stream, socket_address, _client_tcp_stream) = connect().await;
+
+        let start = Instant::now();
+
+        handle_stream(tcp_stream, socket_address, environment_.clone()).await;
+
+        // The request deadline is exceeded before the header read one.
+        assert!(start.elapsed() < LONG_DEADLINE);
+
+        assert!(has_timeouts(&environment_, [0, 0, 1, 0]));
+
+        return ();
+    }
+
+    #[infrastructure::tokio::test(crate = "infrastructure::tokio")]
+    async fn client_not_reading_exceeds_response_write_deadline() -> () {
+        let environment_ = environment(Deadlines {
+            header_read: LONG_DEADLINE,
+            body_read: LONG_DEADLINE,
+            request: LONG_DEADLINE,
+            response_write: SHORT_DEADLINE,
+        });
+
+        let (mut tcp_stream, _, _client_tcp_stream) = connect().await;
+
+        // The response does not fit into the buffers, so, it is not written, until the client reads it.
+        let error = respond(&mut tcp_stream, &environment_, &vec![0_u8; 200000])
+            .await
+            .err()
+            .unwrap();
+
+        assert_eq!(timeout_of(error.as_ref()), Some(Timeout::ResponseWrite));
+
+        assert!(has_timeouts(&environment_, [0, 0, 0, 1]));
+
+        return ();
+    }
+
+    #[test]
+    fn algorithm_of_server_preference_is_negotiated() -> () {
+        let server_algorithms = [Algorithm::Argon2id, Algorithm::Sha256, Algorithm::Blake3];
+
+        assert_eq!(
+            negotiate_algorithm(&server_algorithms, &[Algorithm::Blake3, Algorithm::Sha256]),
+            Some(Algorithm::Sha256)
+        );
+
+        assert_eq!(
+            negotiate_algorithm(&server_algorithms, &[Algorithm::Blake3]),
+            Some(Algorithm::Blake3)
+        );
+
+        return ();
+    }
+
+    #[test]
+    fn no_algorithm_is_negotiated_without_common_algorithm() -> () {
+        assert_eq!(
+            negotiate_algorithm(
+                &[Algorithm::Argon2id, Algorithm::Equihash],
+                &[Algorithm::Sha256, Algorithm::TimeLock]
+            ),
+            None
+        );
+
+        return ();
+    }
+
+    #[test]
+    fn default_algorithm_is_negotiated_for_client_without_algorithms() -> () {
+        assert_eq!(
+            negotiate_algorithm(&[Algorithm::Sha256, POW::DEFAULT_ALGORITHM], &[]),
+            Some(POW::DEFAULT_ALGORITHM)
+        );
+
+        assert_eq!(negotiate_algorithm(&[Algorithm::Sha256], &[]), None);
+
+        return ();
+    }
+}
@@ -70,28 +70,61 @@
     clippy::zero_sized_map_values
 )]
 
+#[cfg(not(any(
+    feature = "double_sha256",
+    feature = "sha256",
+    feature = "sha3_256",
+    feature = "blake3",
+    feature = "argon2id",
+    feature = "equihash",
+    feature = "time_lock"
+)))]
+compile_error!("At least one of the proof of work algorithm features must be enabled.");
+
+pub use self::access_token::*;
+pub use self::challenge_table::*;
 pub use self::crypto::*;
 pub use self::encode::*;
 pub use self::error::*;
+pub use self::hashcash::*;
+#[cfg(feature = "tower")]
+pub use self::http_gate::*;
+pub use self::logging::*;
 pub use self::protocol::*;
 pub use self::word_of_wisdom::*;
+#[cfg(feature = "tower")]
+pub use http;
 pub use rand;
 pub use serde;
 pub use tokio;
+pub use tracing;
 pub use uuid;
 
 pub const SERVER_SOCKET_ADDRESS: &'static str = "127.0.0.1:80";
 
 mod protocol {
+    use super::AccessToken;
+    use super::Algorithm;
+    use super::Challenge;
     use super::ErrorKind;
+    use super::ProofPayload;
     use super::Secret;
     use serde::Deserialize;
     use serde::Serialize;
     use std::borrow::Cow;
     use std::error::Error;
+    use std::fmt::Display;
+    use std::fmt::Formatter;
+    use std::fmt::Result as FmtResult;
+    use std::future::Future;
+    use std::io::Result as IoResult;
+    use std::time::Duration;
     use tokio::io::AsyncReadExt;
     use tokio::io::AsyncWriteExt;
     use tokio::net::TcpStream;
+    use tokio::time::timeout;
+    use tracing::debug;
+    use tracing::trace;
     use uuid::Uuid;
 
     pub struct Protocol;
@@ -104,13 +137,27 @@ mod protocol {
         const QUANTITY_OF_BYTES_FOR_BUFFER_SIZE_REPRESENTATION: u8 = 8;
 
         pub async fn send<'a>(
+            tcp_stream: &'a mut TcpStream,
+            data: Vec<u8>,
+        ) -> Result<(), Box<dyn Error + 'static>> {
+            return Self::send_within(tcp_stream, data, None).await;
+        }
+
+        // Fails with the `Timeout::ResponseWrite`, if the peer does not read the data in time.
+        pub async fn send_within<'a>(
             tcp_stream: &'a mut TcpStream,
             mut data: Vec<u8>,
+            write_timeout: Option<Duration>,
         ) -> Result<(), Box<dyn Error + 'static>> {
             let buffer_size = (Self::QUANTITY_OF_BYTES_FOR_BUFFER_SIZE_REPRESENTATION as u64)
                 + (data.len() as u64);
 
             if buffer_size > (Self::MAXIMUM_BUFFER_SIZE) {
+                debug!(
+                    "The frame of {} bytes is not sent: it exceeds the maximum.",
+                    buffer_size
+                );
+
                 return Err(Box::from(ErrorKind::RunTime));
             }
 
@@ -118,9 +165,14 @@ mod protocol {
 
             buffer.append(&mut data);
 
-            if let Err(error) = tcp_stream.write_all(buffer.as_slice()).await {
-                return Err(Box::from(error));
-            }
+            Self::within(
+                write_timeout,
+                Timeout::ResponseWrite,
+                tcp_stream.write_all(buffer.as_slice()),
+            )
+            .await?;
+
+            trace!("The frame of {} bytes is sent.", buffer_size);
 
             return Ok(());
         }
@@ -128,122 +180,531 @@ mod protocol {
         pub async fn receive<'a>(
             tcp_stream: &'a mut TcpStream,
         ) -> Result<Vec<u8>, Box<dyn Error + 'static>> {
-            let mut buffer: Vec<u8> = vec![];
+            return Self::receive_within(tcp_stream, None, None).await;
+        }
 
-            let mut temporary_buffer: Vec<u8> = vec![];
+        // The frame is read exactly, so, the bytes after it stay in the stream, as the bytes
+        // of the upstream of the spliced connection do. The header and the body are read within
+        // their own timeouts, so, the peer, that stalls after the length, does not hold the connection.
+        pub async fn receive_within<'a>(
+            tcp_stream: &'a mut TcpStream,
+            header_read_timeout: Option<Duration>,
+            body_read_timeout: Option<Duration>,
+        ) -> Result<Vec<u8>, Box<dyn Error + 'static>> {
+            let mut buffer_size_representation =
+                [0; Self::QUANTITY_OF_BYTES_FOR_BUFFER_SIZE_REPRESENTATION as usize];
 
-            let mut buffer_size: Option<u64> = None;
+            Self::within(
+                header_read_timeout,
+                Timeout::HeaderRead,
+                tcp_stream.read_exact(&mut buffer_size_representation),
+            )
+            .await?;
 
-            'a: loop {
-                temporary_buffer.clear();
+            let buffer_size = u64::from_be_bytes(buffer_size_representation);
 
-                match tcp_stream.read_buf(&mut temporary_buffer).await {
-                    Ok(bytes_quantity) => {
-                        if bytes_quantity == 0 {
-                            break 'a;
-                        }
+            if buffer_size > Self::MAXIMUM_BUFFER_SIZE {
+                debug!(
+                    "The frame of {} bytes is not received: it exceeds the maximum.",
+                    buffer_size
+                );
 
-                        match buffer_size {
-                            Some(buffer_size_) => {
-                                buffer.append(&mut temporary_buffer);
+                return Err(Box::from(ErrorKind::Logic));
+            }
 
-                                let current_buffer_size = buffer.len() as u64;
+            let data_size = match buffer_size
+                .checked_sub(Self::QUANTITY_OF_BYTES_FOR_BUFFER_SIZE_REPRESENTATION as u64)
+            {
+                Some(data_size_) => data_size_,
+                None => {
+                    debug!(
+                        "The frame of {} bytes is not received: it is shorter than its length.",
+                        buffer_size
+                    );
 
-                                if current_buffer_size < buffer_size_ {
-                                    continue 'a;
-                                }
+                    return Err(Box::from(ErrorKind::Logic));
+                }
+            };
 
-                                if current_buffer_size == buffer_size_ {
-                                    break 'a;
-                                }
+            let mut buffer: Vec<u8> = vec![0; data_size as usize];
 
-                                if current_buffer_size > buffer_size_ {
-                                    return Err(Box::from(ErrorKind::Logic));
-                                }
-                            }
-                            None => {
-                                if bytes_quantity
-                                    < (Self::QUANTITY_OF_BYTES_FOR_BUFFER_SIZE_REPRESENTATION
-                                        as usize)
-                                {
-                                    return Err(Box::from(ErrorKind::Logic));
-                                }
-
-                                let buffer_part: [u8; Self::QUANTITY_OF_BYTES_FOR_BUFFER_SIZE_REPRESENTATION as usize] =
-                                    match temporary_buffer[..(Self::QUANTITY_OF_BYTES_FOR_BUFFER_SIZE_REPRESENTATION as usize)].try_into()
-                                {
-                                    Ok(buffer_part_) => buffer_part_,
-                                    Err(error) => {
-                                        return Err(Box::from(error));
-                                    }
-                                };
-
-                                let mut buffer_size_ = u64::from_be_bytes(buffer_part);
-
-                                if buffer_size_ > Self::MAXIMUM_BUFFER_SIZE {
-                                    return Err(Box::from(ErrorKind::Logic));
-                                }
-
-                                let mut temporary_buffer_ = temporary_buffer[
-                                    (Self::QUANTITY_OF_BYTES_FOR_BUFFER_SIZE_REPRESENTATION as usize)..
-                                ].to_vec();
-
-                                buffer.append(&mut temporary_buffer_);
-
-                                buffer_size_ = match buffer_size_.checked_sub(
-                                    Self::QUANTITY_OF_BYTES_FOR_BUFFER_SIZE_REPRESENTATION as u64,
-                                ) {
-                                    Some(buffer_size__) => buffer_size__,
-                                    None => {
-                                        return Err(Box::from(ErrorKind::Logic));
-                                    }
-                                };
-
-                                buffer_size = Some(buffer_size_);
-
-                                let current_buffer_size = buffer.len() as u64;
-
-                                if current_buffer_size < buffer_size_ {
-                                    continue 'a;
-                                }
-
-                                if current_buffer_size == buffer_size_ {
-                                    break 'a;
-                                }
-
-                                if current_buffer_size > buffer_size_ {
-                                    return Err(Box::from(ErrorKind::Logic));
-                                }
-                            }
-                        }
-                    }
-                    Err(error) => {
-                        return Err(Box::from(error));
-                    }
-                }
-            }
+            Self::within(
+                body_read_timeout,
+                Timeout::BodyRead,
+                tcp_stream.read_exact(buffer.as_mut_slice()),
+            )
+            .await?;
+
+            trace!("The frame of {} bytes is received.", buffer_size);
 
             return Ok(buffer);
         }
+
+        async fn within<F, T>(
+            duration: Option<Duration>,
+            timeout_: Timeout,
+            future: F,
+        ) -> Result<T, Box<dyn Error + 'static>>
+        where
+            F: Future<Output = IoResult<T>>,
+        {
+            let result = match duration {
+                Some(duration_) => match timeout(duration_, future).await {
+                    Ok(result_) => result_,
+                    Err(_) => {
+                        return Err(Box::from(timeout_));
+                    }
+                },
+                None => future.await,
+            };
+
+            return match result {
+                Ok(value) => Ok(value),
+                Err(error) => Err(Box::from(error)),
+            };
+        }
+    }
+
+    // The phase of the exchange, that is not completed in time.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Timeout {
+        HeaderRead,
+        BodyRead,
+        Request,
+        ResponseWrite,
+    }
+
+    impl Display for Timeout {
+        fn fmt<'a>(&'a self, formatter: &'a mut Formatter<'_>) -> FmtResult {
+            return match *self {
+                Self::HeaderRead => write!(formatter, "The header read timeout is exceeded."),
+                Self::BodyRead => write!(formatter, "The body read timeout is exceeded."),
+                Self::Request => write!(formatter, "The request timeout is exceeded."),
+                Self::ResponseWrite => {
+                    write!(formatter, "The response write timeout is exceeded.")
+                }
+            };
+        }
     }
 
+    impl Error for Timeout {}
+
     #[derive(Debug, Serialize, Deserialize)]
     pub enum Request<'a> {
-        Challenge { token: Cow<'a, Uuid> },
-        WordOfWisdom { token: Cow<'a, Uuid>, result: u64 },
+        Challenge {
+            token: Cow<'a, Uuid>,
+            // The algorithms supported by the client. Is empty for the clients, that do not negotiate.
+            #[serde(default)]
+            algorithms: Vec<Algorithm>,
+        },
+        WordOfWisdom {
+            token: Cow<'a, Uuid>,
+            proof: ProofPayload,
+            // Is empty for the clients, that do not filter.
+            #[serde(default)]
+            filter: QuoteFilter,
+        },
+        // The redemption of the challenge for the admission to the upstream of the proxy.
+        Admission {
+            token: Cow<'a, Uuid>,
+            proof: ProofPayload,
+            // The connection is spliced to the upstream right after the admission, otherwise the ticket is issued.
+            keep_connection: bool,
+        },
+        // The follow-up connection, that presents the ticket.
+        Enter {
+            ticket: Cow<'a, Uuid>,
+        },
+        // The redemption of the challenge for the access token only.
+        Admit {
+            token: Cow<'a, Uuid>,
+            proof: ProofPayload,
+        },
+        // The WordOfWisdom request without the new work.
+        Access {
+            access_token: AccessToken,
+            #[serde(default)]
+            filter: QuoteFilter,
+        },
     }
 
     #[derive(Debug, Serialize, Deserialize)]
     pub enum Response<'a> {
-        Challenge { secret: Secret },
+        Challenge { challenge: Challenge },
+        // The server does not support any of the algorithms of the client.
+        AlgorithmMismatch { algorithms: Vec<Algorithm> },
         WordOfWisdom { word_of_wisdom: WordOfWisdom<'a> },
+        Admission { admission: Admission },
+        // Is None, if the proof is not verified.
+        Admit { access_token: Option<AccessToken> },
     }
 
+    // The messages of the protocol before the negotiation of the algorithm. The server answers the Challenge request
+    // without the algorithms in this shape, so, the clients, that do not negotiate, keep working: the challenge is
+    // the double SHA-256 nonce of the default difficulty, that is proved by the single u64 result.
     #[derive(Debug, Serialize, Deserialize)]
-    pub enum WordOfWisdom<'a> {
+    pub enum LegacyRequest<'a> {
+        Challenge { token: Cow<'a, Uuid> },
+        WordOfWisdom { token: Cow<'a, Uuid>, result: u64 },
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub enum LegacyResponse<'a> {
+        Challenge {
+            secret: Secret,
+        },
+        WordOfWisdom {
+            word_of_wisdom: LegacyWordOfWisdom<'a>,
+        },
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub enum LegacyWordOfWisdom<'a> {
         Result { result: Cow<'a, str> },
         Fail,
     }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub enum Admission {
+        // The connection is spliced to the upstream right after the response.
+        Admitted,
+        // Is presented once by the follow-up connection within the lifetime.
+        Ticket { ticket: Uuid, lifetime_seconds: u64 },
+        Fail,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub enum WordOfWisdom<'a> {
+        Result {
+            result: Payload<'a>,
+            // Is issued for the redeemed challenge, if the server issues the access tokens.
+            #[serde(default)]
+            access_token: Option<AccessToken>,
+        },
+        Fail,
+        // The proof is verified, but there is no quote, that matches the filter. The challenge is not spent.
+        NoMatch,
+        // The access token is forged, expired or exhausted, so, the new challenge is required.
+        AccessDenied,
+    }
+
+    // The protected resource, that is returned for the verified proof.
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub enum Payload<'a> {
+        Text {
+            text: Cow<'a, str>,
+        },
+        Binary {
+            media_type: Cow<'a, str>,
+            data: Cow<'a, [u8]>,
+        },
+        Quote {
+            quote: Quote<'a>,
+        },
+    }
+
+    impl<'a> Payload<'a> {
+        // For the clients, that receive the text only.
+        pub fn into_text(self) -> Cow<'a, str> {
+            return match self {
+                Self::Text { text } => text,
+                Self::Binary {
+                    media_type: _,
+                    data,
+                } => Cow::Owned(String::from_utf8_lossy(data.as_ref()).into_owned()),
+                Self::Quote { quote } => quote.text,
+            };
+        }
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct Quote<'a> {
+        pub text: Cow<'a, str>,
+        #[serde(default)]
+        pub author: Option<Cow<'a, str>>,
+        #[serde(default)]
+        pub source: Option<Cow<'a, str>>,
+        // The language tag, as "en".
+        #[serde(default)]
+        pub language: Option<Cow<'a, str>>,
+        #[serde(default)]
+        pub tags: Vec<Cow<'a, str>>,
+    }
+
+    impl<'a> Quote<'a> {
+        pub fn new(text: Cow<'a, str>) -> Self {
+            return Self {
+                text,
+                author: None,
+                source: None,
+                language: None,
+                tags: vec![],
+            };
+        }
+    }
+
+    // The quote matches all of the specified conditions. The tag and the language are compared case-insensitively,
+    // the length is the quantity of characters of the text.
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct QuoteFilter {
+        pub tag: Option<String>,
+        pub language: Option<String>,
+        pub maximum_length: Option<u32>,
+    }
+
+    impl QuoteFilter {
+        pub fn matches<'a>(&'a self, quote: &'a Quote<'a>) -> bool {
+            if let Some(ref tag) = self.tag {
+                if !quote
+                    .tags
+                    .iter()
+                    .any(|tag_| tag_.eq_ignore_ascii_case(tag.as_str()))
+                {
+                    return false;
+                }
+            }
+
+            if let Some(ref language) = self.language {
+                if !quote
+                    .language
+                    .as_ref()
+                    .is_some_and(|language_| language_.eq_ignore_ascii_case(language.as_str()))
+                {
+                    return false;
+                }
+            }
+
+            if let Some(maximum_length) = self.maximum_length {
+                if quote.text.chars().count() > (maximum_length as usize) {
+                    return false;
+                }
+            }
+
+            return true;
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::super::Challenge;
+        use super::super::Difficulty;
+        use super::super::Proof;
+        use super::super::Serializer;
+        use super::super::POW;
+        use super::Algorithm;
+        use super::LegacyRequest;
+        use super::Payload;
+        use super::ProofPayload;
+        use super::Quote;
+        use super::QuoteFilter;
+        use super::Request;
+        use super::Response;
+        use super::WordOfWisdom;
+        use std::borrow::Cow;
+        use uuid::Uuid;
+
+        #[test]
+        fn legacy_challenge_request_is_read_without_algorithms() -> () {
+            let token = Uuid::new_v4();
+
+            let data = Serializer::serialize(&LegacyRequest::Challenge {
+                token: Cow::Owned(token),
+            })
+            .unwrap();
+
+            match Serializer::deserialize::<'_, Request>(data.as_slice()).unwrap() {
+                Request::Challenge {
+                    token: token_,
+                    algorithms,
+                } => {
+                    assert_eq!(*token_, token);
+
+                    assert!(algorithms.is_empty());
+                }
+                _ => panic!("The legacy challenge request is read as the other request."),
+            }
+
+            return ();
+        }
+
+        #[test]
+        fn legacy_word_of_wisdom_request_is_read_as_legacy_only() -> () {
+            let data = Serializer::serialize(&LegacyRequest::WordOfWisdom {
+                token: Cow::Owned(Uuid::new_v4()),
+                result: 42,
+            })
+            .unwrap();
+
+            assert!(Serializer::deserialize::<'_, Request>(data.as_slice()).is_err());
+
+            assert!(matches!(
+                Serializer::deserialize::<'_, LegacyRequest>(data.as_slice()).unwrap(),
+                LegacyRequest::WordOfWisdom { result: 42, .. }
+            ));
+
+            return ();
+        }
+
+        fn challenge() -> Challenge {
+            return POW::new(Algorithm::DoubleSha256)
+                .unwrap()
+                .generate_challenge(Difficulty::I);
+        }
+
+        fn quote() -> Quote<'static> {
+            let mut quote = Quote::new(Cow::Borrowed("Être ou ne pas être."));
+
+            quote.language = Some(Cow::Borrowed("fr"));
+
+            quote.tags = vec![Cow::Borrowed("Life"), Cow::Borrowed("theatre")];
+
+            return quote;
+        }
+
+        #[test]
+        fn proof_payload_is_decoded() -> () {
+            let challenge = challenge();
+
+            let proof = Proof::Nonce(42_u64.to_be_bytes().to_vec());
+
+            let proof_payload = ProofPayload::encode(Algorithm::DoubleSha256, &proof).unwrap();
+
+            let data = Serializer::serialize(&proof_payload).unwrap();
+
+            let proof_payload_ =
+                Serializer::deserialize::<'_, ProofPayload>(data.as_slice()).unwrap();
+
+            assert_eq!(proof_payload_, proof_payload);
+
+            assert_eq!(proof_payload_.decode(&challenge), Some(proof));
+
+            return ();
+        }
+
+        #[test]
+        fn proof_payload_of_other_version_is_not_decoded() -> () {
+            let challenge = challenge();
+
+            let mut proof_payload =
+                ProofPayload::encode(Algorithm::DoubleSha256, &Proof::Nonce(vec![0; 8])).unwrap();
+
+            proof_payload.version = ProofPayload::VERSION + 1;
+
+            assert_eq!(proof_payload.decode(&challenge), None);
+
+            proof_payload.version = 0;
+
+            assert_eq!(proof_payload.decode(&challenge), None);
+
+            return ();
+        }
+
+        #[test]
+        fn malformed_proof_payload_is_not_decoded() -> () {
+            let challenge = challenge();
+
+            let mut proof_payload =
+                ProofPayload::encode(Algorithm::DoubleSha256, &Proof::Nonce(vec![0; 8])).unwrap();
+
+            proof_payload.algorithm = Algorithm::Sha256;
+
+            assert_eq!(proof_payload.decode(&challenge), None);
+
+            proof_payload.algorithm = Algorithm::DoubleSha256;
+
+            proof_payload.data = vec![0; Algorithm::DoubleSha256.maximum_proof_length() + 1];
+
+            assert_eq!(proof_payload.decode(&challenge), None);
+
+            proof_payload.data = vec![0xc1];
+
+            assert_eq!(proof_payload.decode(&challenge), None);
+
+            return ();
+        }
+
+        #[test]
+        fn payload_is_read_back() -> () {
+            let payloads = vec![
+                Payload::Text {
+                    text: Cow::Borrowed("text"),
+                },
+                Payload::Binary {
+                    media_type: Cow::Borrowed("application/octet-stream"),
+                    data: Cow::Borrowed([0, 159, 146, 150].as_slice()),
+                },
+                Payload::Quote { quote: quote() },
+            ];
+
+            for payload in payloads.into_iter() {
+                let data = Serializer::serialize(&Response::WordOfWisdom {
+                    word_of_wisdom: WordOfWisdom::Result {
+                        result: payload.clone(),
+                        access_token: None,
+                    },
+                })
+                .unwrap();
+
+                let payload_ =
+                    match Serializer::deserialize::<'_, Response>(data.as_slice()).unwrap() {
+                        Response::WordOfWisdom {
+                            word_of_wisdom:
+                                WordOfWisdom::Result {
+                                    result,
+                                    access_token: None,
+                                },
+                        } => result,
+                        _ => panic!("The response is read as the other response."),
+                    };
+
+                assert_eq!(format!("{:?}", &payload_), format!("{:?}", &payload));
+            }
+
+            return ();
+        }
+
+        #[test]
+        fn quote_filter_matches_all_conditions() -> () {
+            let quote = quote();
+
+            assert!(QuoteFilter::default().matches(&quote));
+
+            let filter = QuoteFilter {
+                tag: Some("LIFE".to_string()),
+                language: Some("FR".to_string()),
+                maximum_length: Some(20),
+            };
+
+            assert!(filter.matches(&quote));
+
+            // The length is the quantity of characters, not of bytes.
+            assert!(!QuoteFilter {
+                maximum_length: Some(19),
+                ..filter.clone()
+            }
+            .matches(&quote));
+
+            assert!(!QuoteFilter {
+                tag: Some("love".to_string()),
+                ..filter.clone()
+            }
+            .matches(&quote));
+
+            assert!(!QuoteFilter {
+                language: Some("en".to_string()),
+                ..filter
+            }
+            .matches(&quote));
+
+            assert!(!QuoteFilter {
+                language: Some("fr".to_string()),
+                ..QuoteFilter::default()
+            }
+            .matches(&Quote::new(Cow::Borrowed("Be yourself."))));
+
+            return ();
+        }
+    }
 }
 
 mod encode {
@@ -302,126 +763,5842 @@ mod encode {
 }
 
 mod crypto {
+    use super::ErrorKind;
     use super::Serializer;
+    #[cfg(feature = "argon2id")]
+    use argon2::Algorithm as Argon2Algorithm;
+    #[cfg(feature = "argon2id")]
+    use argon2::Argon2;
+    #[cfg(feature = "argon2id")]
+    use argon2::Block as Argon2Block;
+    #[cfg(feature = "argon2id")]
+    use argon2::Params as Argon2Params;
+    #[cfg(feature = "argon2id")]
+    use argon2::Version as Argon2Version;
+    #[cfg(feature = "blake3")]
+    use blake3::Hasher as Blake3Hasher;
+    #[cfg(any(
+        feature = "sha3_256",
+        feature = "blake3",
+        feature = "equihash",
+        feature = "time_lock"
+    ))]
     use crypto::digest::Digest;
+    #[cfg(any(feature = "equihash", feature = "time_lock"))]
     use crypto::sha2::Sha256;
+    #[cfg(feature = "sha3_256")]
+    use crypto::sha3::Sha3;
+    #[cfg(feature = "time_lock")]
+    use num_bigint::BigUint;
+    #[cfg(feature = "time_lock")]
+    use num_bigint::RandPrime;
     use rand::thread_rng;
     use rand::Rng;
     use serde::Deserialize;
     use serde::Serialize;
+    #[cfg(any(feature = "double_sha256", feature = "sha256"))]
+    use sha2::compress256;
+    #[cfg(any(feature = "double_sha256", feature = "sha256"))]
+    use sha2::digest::consts::U64;
+    #[cfg(any(feature = "double_sha256", feature = "sha256"))]
+    use sha2::digest::generic_array::GenericArray;
+    use std::collections::hash_map::Entry;
+    use std::collections::HashMap;
     use std::error::Error;
+    use std::fmt::Debug;
+    use std::fmt::Display;
+    use std::fmt::Formatter;
+    use std::fmt::Result as FmtResult;
+    use std::panic::catch_unwind;
+    use std::panic::AssertUnwindSafe;
+    use std::str::FromStr;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::atomic::Ordering;
+    use std::sync::mpsc::channel;
+    use std::sync::mpsc::Receiver as WorkReceiver;
+    use std::sync::mpsc::Sender as WorkSender;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use std::sync::PoisonError;
+    use std::thread::Builder;
+    use std::time::Duration;
+    use std::time::Instant;
+    use tokio::sync::mpsc::Sender;
+    use tokio::sync::oneshot;
+    use tokio::task::spawn_blocking;
     use uuid::Uuid;
 
+    pub trait ProofOfWork: Send {
+        fn algorithm<'a>(&'a self) -> Algorithm;
+
+        fn generate_challenge<'a>(&'a self, difficulty: Difficulty) -> Challenge {
+            return Challenge {
+                secret: Secret {
+                    value: Uuid::new_v4(),
+                },
+                difficulty,
+                algorithm: self.algorithm(),
+                parameters: Parameters::None,
+                puzzles_quantity: 1,
+            };
+        }
+
+        // Performs the work for the single nonce.
+        fn attempt<'a>(
+            &'a mut self,
+            challenge: &'a Challenge,
+            nonce: u64,
+        ) -> Result<Attempt, Box<dyn Error + 'static>>;
+
+        fn solve<'a>(
+            &'a mut self,
+            challenge: &'a Challenge,
+        ) -> Result<Proof, Box<dyn Error + 'static>> {
+            let proof = 'a: loop {
+                let attempt = self.attempt(challenge, thread_rng().gen_range(0..u64::MAX))?;
+
+                if let Some(proof_) = attempt.proof {
+                    break 'a proof_;
+                }
+
+                continue 'a;
+            };
+
+            return Ok(proof);
+        }
+
+        fn verify<'a>(
+            &'a mut self,
+            challenge: &'a Challenge,
+            proof: &'a Proof,
+        ) -> Result<bool, Box<dyn Error + 'static>>;
+
+        fn describe_cost<'a>(&'a self, difficulty: Difficulty) -> Cost;
+    }
+
+    // The facade over the implementation of the negotiated algorithm.
     pub struct POW {
-        sha256: Sha256,
-        difficulty: Difficulty,
-        result_hash: Vec<u8>,
+        proof_of_work: Box<dyn ProofOfWork>,
     }
 
     impl POW {
         pub const DEFAULT_DIFFICULTY: Difficulty = Difficulty::III;
+        pub const DEFAULT_ALGORITHM: Algorithm = Algorithm::DoubleSha256;
 
-        pub fn new(difficulty: Difficulty) -> Self {
-            let sha256 = Sha256::new();
+        pub fn new(algorithm: Algorithm) -> Result<Self, Box<dyn Error + 'static>> {
+            return Self::new_with_parameters(algorithm, Parameters::default_for(algorithm));
+        }
+
+        // The parameters are used for the generation of the challenges only,
+        // the solution and the verification use the parameters of the challenge.
+        pub fn new_with_parameters(
+            algorithm: Algorithm,
+            parameters: Parameters,
+        ) -> Result<Self, Box<dyn Error + 'static>> {
+            if !parameters.is_compatible_with(algorithm) {
+                return Err(Box::from(ErrorKind::Logic));
+            }
 
-            let bytes_quantity = sha256.output_bytes();
+            let proof_of_work: Box<dyn ProofOfWork> = match (algorithm, parameters) {
+                #[cfg(feature = "double_sha256")]
+                (Algorithm::DoubleSha256, _) => Box::new(Sha256ProofOfWork::new(algorithm, 2)),
+                #[cfg(feature = "sha256")]
+                (Algorithm::Sha256, _) => Box::new(Sha256ProofOfWork::new(algorithm, 1)),
+                #[cfg(feature = "sha3_256")]
+                (Algorithm::Sha3_256, _) => {
+                    Box::new(HashProofOfWork::new(algorithm, Sha3::sha3_256(), 1))
+                }
+                #[cfg(feature = "blake3")]
+                (Algorithm::Blake3, _) => {
+                    Box::new(HashProofOfWork::new(algorithm, Blake3::new(), 1))
+                }
+                #[cfg(feature = "argon2id")]
+                (Algorithm::Argon2id, Parameters::MemoryHard(memory_hard_parameters)) => {
+                    memory_hard_parameters.validate()?;
 
-            return Self {
-                sha256,
-                difficulty,
-                result_hash: vec![0; bytes_quantity],
+                    Box::new(Argon2idProofOfWork::new(memory_hard_parameters))
+                }
+                #[cfg(feature = "equihash")]
+                (
+                    Algorithm::Equihash,
+                    Parameters::GeneralizedBirthday(generalized_birthday_parameters),
+                ) => {
+                    generalized_birthday_parameters.validate()?;
+
+                    Box::new(EquihashProofOfWork::new(generalized_birthday_parameters))
+                }
+                // The parameters are validated with the challenge, because they are not known to the solver in advance.
+                #[cfg(feature = "time_lock")]
+                (
+                    Algorithm::TimeLock,
+                    Parameters::SequentialSquaring(sequential_squaring_parameters),
+                ) => Box::new(TimeLockProofOfWork::new(sequential_squaring_parameters)),
+                #[allow(unreachable_patterns)]
+                _ => {
+                    return Err(Box::from(ErrorKind::Logic));
+                }
             };
+
+            return Ok(Self { proof_of_work });
         }
 
-        pub fn find_nonce<'a>(
-            &'a mut self,
-            secret: &'a Secret,
-        ) -> Result<u64, Box<dyn Error + 'static>> {
-            let data = Serializer::serialize(secret)?;
+        // The algorithms, which implementations are enabled by the cargo features.
+        pub fn supported_algorithms() -> Vec<Algorithm> {
+            return Algorithm::ALL
+                .iter()
+                .copied()
+                .filter(|algorithm| Self::is_supported(*algorithm))
+                .collect();
+        }
+
+        pub fn is_supported(algorithm: Algorithm) -> bool {
+            return match algorithm {
+                Algorithm::DoubleSha256 => cfg!(feature = "double_sha256"),
+                Algorithm::Sha256 => cfg!(feature = "sha256"),
+                Algorithm::Sha3_256 => cfg!(feature = "sha3_256"),
+                Algorithm::Blake3 => cfg!(feature = "blake3"),
+                Algorithm::Argon2id => cfg!(feature = "argon2id"),
+                Algorithm::Equihash => cfg!(feature = "equihash"),
+                Algorithm::TimeLock => cfg!(feature = "time_lock"),
+            };
+        }
 
-            let random_number = 'a: loop {
-                let random_number_ = thread_rng().gen_range(0..u64::MAX);
+        pub fn algorithm<'a>(&'a self) -> Algorithm {
+            return self.proof_of_work.algorithm();
+        }
 
-                if self.verify_nonce_(data.as_slice(), random_number_) {
-                    break 'a random_number_;
+        // The SHA-256 backend, that is selected at runtime by the detected processor features.
+        pub fn sha256_backend() -> &'static str {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            {
+                if is_x86_feature_detected!("sha")
+                    && is_x86_feature_detected!("sse2")
+                    && is_x86_feature_detected!("ssse3")
+                    && is_x86_feature_detected!("sse4.1")
+                {
+                    return "SHA-NI";
                 }
+            }
 
-                continue 'a;
-            };
+            #[cfg(target_arch = "aarch64")]
+            {
+                if std::arch::is_aarch64_feature_detected!("sha2") {
+                    return "ARMv8 SHA2";
+                }
+            }
 
-            return Ok(random_number);
+            return "portable";
         }
 
-        pub fn verify_nonce<'a>(
+        pub fn generate_challenge<'a>(&'a self, difficulty: Difficulty) -> Challenge {
+            return self.proof_of_work.generate_challenge(difficulty);
+        }
+
+        // Requires the given quantity of the distinct solutions of the same difficulty. The solve time
+        // of the several puzzles of the lower difficulty varies much less, than of the single puzzle.
+        pub fn generate_multi_puzzle_challenge<'a>(
+            &'a self,
+            difficulty: Difficulty,
+            puzzles_quantity: u16,
+        ) -> Result<Challenge, Box<dyn Error + 'static>> {
+            let mut challenge = self.proof_of_work.generate_challenge(difficulty);
+
+            challenge.puzzles_quantity = puzzles_quantity;
+
+            self.check(&challenge)?;
+
+            return Ok(challenge);
+        }
+
+        pub fn attempt<'a>(
             &'a mut self,
-            secret: &'a Secret,
+            challenge: &'a Challenge,
             nonce: u64,
-        ) -> Result<bool, Box<dyn Error + 'static>> {
-            let data = Serializer::serialize(secret)?;
+        ) -> Result<Attempt, Box<dyn Error + 'static>> {
+            self.check(challenge)?;
 
-            return Ok(self.verify_nonce_(data.as_slice(), nonce));
+            return self.proof_of_work.attempt(challenge, nonce);
         }
 
-        fn verify_nonce_<'a>(&'a mut self, secret: &'a [u8], nonce: u64) -> bool {
-            let mut buffer: Vec<u8> = vec![];
+        pub fn find_nonce<'a>(
+            &'a mut self,
+            challenge: &'a Challenge,
+        ) -> Result<Proof, Box<dyn Error + 'static>> {
+            self.check(challenge)?;
+
+            if challenge.puzzles_quantity == 1 {
+                return self.proof_of_work.solve(challenge);
+            }
+
+            let mut proofs: Vec<Proof> = Vec::with_capacity(challenge.puzzles_quantity as usize);
+
+            'a: while proofs.len() < (challenge.puzzles_quantity as usize) {
+                let proof = self.proof_of_work.solve(challenge)?;
 
-            buffer.extend_from_slice(secret);
+                if proofs.contains(&proof) {
+                    continue 'a;
+                }
+
+                proofs.push(proof);
+            }
 
-            buffer.extend_from_slice(nonce.to_be_bytes().as_slice());
+            return Ok(Proof::Multiple(proofs));
+        }
 
-            let zero_bytes_quantity = self.difficulty.into_number();
+        pub fn solve<'a>(
+            &'a mut self,
+            challenge: &'a Challenge,
+        ) -> Result<ProofPayload, Box<dyn Error + 'static>> {
+            let proof = self.find_nonce(challenge)?;
 
-            self.sha256.reset();
+            return ProofPayload::encode(challenge.algorithm, &proof);
+        }
 
-            self.sha256.input(buffer.as_slice());
+        // The malformed payload is not verified.
+        pub fn verify<'a>(
+            &'a mut self,
+            challenge: &'a Challenge,
+            proof_payload: &'a ProofPayload,
+        ) -> Result<bool, Box<dyn Error + 'static>> {
+            self.check(challenge)?;
 
-            self.sha256.result(self.result_hash.as_mut_slice());
+            return match proof_payload.decode(challenge) {
+                Some(proof) => self.verify_nonce(challenge, &proof),
+                None => Ok(false),
+            };
+        }
 
-            self.sha256.reset();
+        pub fn verify_nonce<'a>(
+            &'a mut self,
+            challenge: &'a Challenge,
+            proof: &'a Proof,
+        ) -> Result<bool, Box<dyn Error + 'static>> {
+            self.check(challenge)?;
 
-            self.sha256.input(self.result_hash.as_slice());
+            if challenge.puzzles_quantity == 1 {
+                return self.proof_of_work.verify(challenge, proof);
+            }
 
-            self.sha256.result(self.result_hash.as_mut_slice());
+            let proofs = match *proof {
+                Proof::Multiple(ref proofs_) => proofs_,
+                Proof::Nonce(_)
+                | Proof::Solution {
+                    nonce: _,
+                    indices: _,
+                }
+                | Proof::Residue(_) => {
+                    return Ok(false);
+                }
+            };
 
-            let mut zero_bytes_quantity_counter: usize = 0;
+            if proofs.len() != (challenge.puzzles_quantity as usize) {
+                return Ok(false);
+            }
 
-            for i in 0..zero_bytes_quantity {
-                if self.result_hash[i] == 0 {
-                    zero_bytes_quantity_counter += 1;
+            for (index, proof_) in proofs.iter().enumerate() {
+                if proofs[..index].contains(proof_)
+                    || !self.proof_of_work.verify(challenge, proof_)?
+                {
+                    return Ok(false);
                 }
             }
 
-            if zero_bytes_quantity_counter == zero_bytes_quantity {
-                return true;
+            return Ok(true);
+        }
+
+        pub fn describe_cost<'a>(&'a self, difficulty: Difficulty) -> Cost {
+            return self.proof_of_work.describe_cost(difficulty);
+        }
+
+        fn check<'a>(&'a self, challenge: &'a Challenge) -> Result<(), Box<dyn Error + 'static>> {
+            // The time-lock puzzle has the single solution.
+            if challenge.algorithm != self.proof_of_work.algorithm()
+                || !challenge.parameters.is_compatible_with(challenge.algorithm)
+                || challenge.puzzles_quantity == 0
+                || challenge.puzzles_quantity > Challenge::MAXIMUM_PUZZLES_QUANTITY
+                || (challenge.algorithm == Algorithm::TimeLock && challenge.puzzles_quantity != 1)
+            {
+                return Err(Box::from(ErrorKind::Logic));
             }
 
-            return false;
+            return Ok(());
         }
     }
 
-    pub enum Difficulty {
-        I,
-        // < 10 sec.
-        II,
-        // < 5 min.
-        III,
-        IV,
+    // Verifies the many proofs at once: the verifications are split between the long-lived worker threads,
+    // and every worker keeps the POW of every algorithm for all of its verifications.
+    pub struct BatchVerifier {
+        threads_quantity: usize,
+        sender: WorkSender<Work>,
     }
 
-    impl Difficulty {
-        fn into_number<'a>(&'a self) -> usize {
+    struct Work {
+        verifications: Vec<(Challenge, ProofPayload)>,
+        sender: oneshot::Sender<Vec<bool>>,
+    }
+
+    impl BatchVerifier {
+        // The workers are stopped, when the verifier is dropped.
+        pub fn new(threads_quantity: usize) -> Result<Self, Box<dyn Error + 'static>> {
+            let threads_quantity_ = threads_quantity.max(1);
+
+            let (sender, receiver) = channel::<Work>();
+
+            let receiver_ = Arc::new(Mutex::new(receiver));
+
+            for index in 0..threads_quantity_ {
+                let receiver__ = receiver_.clone();
+
+                if let Err(error) = Builder::new()
+                    .name(format!("verifier-{}", index))
+                    .spawn(move || Self::work(receiver__))
+                {
+                    return Err(Box::from(error));
+                }
+            }
+
+            return Ok(Self {
+                threads_quantity: threads_quantity_,
+                sender,
+            });
+        }
+
+        // The results are in the order of the verifications. The verification, that fails with the error
+        // (or which worker panics), is not verified.
+        pub async fn verify<'a>(
+            &'a self,
+            verifications: Vec<(Challenge, ProofPayload)>,
+        ) -> Vec<bool> {
+            let verifications_quantity = verifications.len();
+
+            let chunk_length = verifications_quantity
+                .div_ceil(self.threads_quantity)
+                .max(1);
+
+            let mut receivers: Vec<(usize, oneshot::Receiver<Vec<bool>>)> = vec![];
+
+            let mut verifications_ = verifications.into_iter();
+
+            'a: loop {
+                let chunk: Vec<(Challenge, ProofPayload)> =
+                    verifications_.by_ref().take(chunk_length).collect();
+
+                if chunk.is_empty() {
+                    break 'a;
+                }
+
+                let (sender, receiver) = oneshot::channel();
+
+                receivers.push((chunk.len(), receiver));
+
+                // The work is not sent, only if all of the workers are stopped, so, its result is not received.
+                let _ = self.sender.send(Work {
+                    verifications: chunk,
+                    sender,
+                });
+            }
+
+            let mut results = Vec::with_capacity(verifications_quantity);
+
+            for (chunk_length_, receiver) in receivers.into_iter() {
+                match receiver.await {
+                    Ok(results_) => {
+                        results.extend(results_);
+                    }
+                    Err(_) => {
+                        results.extend(vec![false; chunk_length_]);
+                    }
+                }
+            }
+
+            return results;
+        }
+
+        fn work(receiver: Arc<Mutex<WorkReceiver<Work>>>) -> () {
+            let mut p_o_ws: HashMap<Algorithm, POW> = HashMap::new();
+
+            'a: loop {
+                // The lock is held only while the work is waited for, so, the workers take the works by turns.
+                let work = match receiver
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .recv()
+                {
+                    Ok(work_) => work_,
+                    Err(_) => {
+                        break 'a;
+                    }
+                };
+
+                match catch_unwind(AssertUnwindSafe(|| {
+                    Self::verify_chunk(&mut p_o_ws, work.verifications.as_slice())
+                })) {
+                    Ok(results) => {
+                        let _ = work.sender.send(results);
+                    }
+                    Err(_) => {
+                        // The POW, that panicked, is not reused. The result is not sent, so, the chunk is not verified.
+                        p_o_ws.clear();
+                    }
+                }
+            }
+
+            return ();
+        }
+
+        fn verify_chunk<'a>(
+            p_o_ws: &'a mut HashMap<Algorithm, POW>,
+            verifications: &'a [(Challenge, ProofPayload)],
+        ) -> Vec<bool> {
+            return verifications
+                .iter()
+                .map(|(challenge, proof_payload)| {
+                    let p_o_w = match p_o_ws.entry(challenge.algorithm) {
+                        Entry::Occupied(entry) => entry.into_mut(),
+                        Entry::Vacant(entry) => match POW::new(challenge.algorithm) {
+                            Ok(p_o_w_) => entry.insert(p_o_w_),
+                            Err(_) => {
+                                return false;
+                            }
+                        },
+                    };
+
+                    return matches!(p_o_w.verify(challenge, proof_payload), Ok(true));
+                })
+                .collect();
+        }
+    }
+
+    // Stops the solving, that is started with the token or its clones.
+    #[derive(Debug, Clone, Default)]
+    pub struct CancellationToken {
+        is_cancelled: Arc<AtomicBool>,
+    }
+
+    impl CancellationToken {
+        pub fn new() -> Self {
+            return Self::default();
+        }
+
+        pub fn cancel<'a>(&'a self) -> () {
+            self.is_cancelled.store(true, Ordering::Relaxed);
+
+            return ();
+        }
+
+        pub fn is_cancelled<'a>(&'a self) -> bool {
+            return self.is_cancelled.load(Ordering::Relaxed);
+        }
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    pub struct Progress {
+        pub attempts_quantity: u64,
+        pub solved_puzzles_quantity: u16,
+        pub elapsed: Duration,
+        pub hash_rate: f64,
+        // The attempts are independent, so, the expected time of the rest of the work does not depend
+        // on the attempts, that are already made. Unknown until the first attempt is finished.
+        pub estimated_remaining_time: Option<Duration>,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Interruption {
+        Cancelled,
+        DeadlineExceeded,
+    }
+
+    impl Display for Interruption {
+        fn fmt<'a>(&'a self, formatter: &'a mut Formatter<'_>) -> FmtResult {
             return match *self {
-                Self::I => 1,
-                Self::II => 2,
-                Self::III => 3,
-                Self::IV => 4,
+                Self::Cancelled => write!(formatter, "The solving is cancelled."),
+                Self::DeadlineExceeded => write!(formatter, "The solving deadline is exceeded."),
             };
         }
     }
 
-    #[derive(Debug, Serialize, Deserialize, Clone)]
-    pub struct Secret {
-        pub value: Uuid,
+    impl Error for Interruption {}
+
+    // Solves the challenge on the blocking thread, so, the async executor is not blocked. The cancellation
+    // and the deadline are checked, and the progress is reported, between the batches of attempts, which take
+    // about the batch duration (but not less than the single attempt: the time-lock puzzle is the single attempt).
+    // The solving is cancelled also, if the future is dropped. The solving error is the Interruption, if it is interrupted.
+    pub struct Solver {
+        pub cancellation_token: CancellationToken,
+        pub deadline: Option<Instant>,
+        // The progress is not reported, if the receiver lags behind.
+        pub progress_sender: Option<Sender<Progress>>,
+        pub progress_interval: Duration,
+    }
+
+    impl Solver {
+        pub const DEFAULT_PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+        const BATCH_DURATION: Duration = Duration::from_millis(10);
+
+        pub fn new(cancellation_token: CancellationToken) -> Self {
+            return Self {
+                cancellation_token,
+                deadline: None,
+                progress_sender: None,
+                progress_interval: Self::DEFAULT_PROGRESS_INTERVAL,
+            };
+        }
+
+        pub async fn solve<'a>(
+            &'a self,
+            mut p_o_w: POW,
+            challenge: Challenge,
+        ) -> Result<ProofPayload, Box<dyn Error + 'static>> {
+            let cancellation_token = self.cancellation_token.clone();
+
+            let drop_guard = DropGuard {
+                cancellation_token: CancellationToken::new(),
+            };
+
+            let dropped_token = drop_guard.cancellation_token.clone();
+
+            let deadline = self.deadline;
+
+            let progress_sender = self.progress_sender.clone();
+
+            let progress_interval = self.progress_interval;
+
+            let result = spawn_blocking(move || {
+                return Self::search(
+                    &mut p_o_w,
+                    &challenge,
+                    &[cancellation_token, dropped_token],
+                    deadline,
+                    progress_sender.as_ref(),
+                    progress_interval,
+                );
+            })
+            .await;
+
+            return match result {
+                Ok(Ok(proof_payload)) => Ok(proof_payload),
+                Ok(Err(error)) => Err(error),
+                Err(error) => Err(Box::from(error)),
+            };
+        }
+
+        // The error is returned from the blocking thread.
+        fn search<'a>(
+            p_o_w: &'a mut POW,
+            challenge: &'a Challenge,
+            cancellation_tokens: &'a [CancellationToken],
+            deadline: Option<Instant>,
+            progress_sender: Option<&'a Sender<Progress>>,
+            progress_interval: Duration,
+        ) -> Result<ProofPayload, Box<dyn Error + Send + Sync + 'static>> {
+            let expected_attempts_quantity = p_o_w
+                .describe_cost(challenge.difficulty)
+                .expected_attempts_quantity;
+
+            let mut proofs: Vec<Proof> = Vec::with_capacity(challenge.puzzles_quantity as usize);
+
+            let mut attempts_quantity: u64 = 0;
+
+            let mut batch_size: u64 = 1;
+
+            let start = Instant::now();
+
+            let mut last_report = start;
+
+            'a: while proofs.len() < (challenge.puzzles_quantity as usize) {
+                if cancellation_tokens
+                    .iter()
+                    .any(CancellationToken::is_cancelled)
+                {
+                    return Err(Box::from(Interruption::Cancelled));
+                }
+
+                if deadline.is_some_and(|deadline_| Instant::now() >= deadline_) {
+                    return Err(Box::from(Interruption::DeadlineExceeded));
+                }
+
+                let batch_start = Instant::now();
+
+                for _ in 0..batch_size {
+                    let attempt =
+                        match p_o_w.attempt(challenge, thread_rng().gen_range(0..u64::MAX)) {
+                            Ok(attempt_) => attempt_,
+                            Err(error) => {
+                                return Err(Box::from(error.to_string()));
+                            }
+                        };
+
+                    attempts_quantity += attempt.attempts_quantity;
+
+                    if let Some(proof) = attempt.proof {
+                        if !proofs.contains(&proof) {
+                            proofs.push(proof);
+                        }
+
+                        if proofs.len() == (challenge.puzzles_quantity as usize) {
+                            break 'a;
+                        }
+                    }
+                }
+
+                if batch_start.elapsed() < Self::BATCH_DURATION {
+                    batch_size = batch_size.saturating_mul(2);
+                }
+
+                if let Some(progress_sender_) = progress_sender {
+                    if last_report.elapsed() >= progress_interval {
+                        last_report = Instant::now();
+
+                        let elapsed = start.elapsed();
+
+                        let hash_rate = (attempts_quantity as f64) / elapsed.as_secs_f64();
+
+                        let remaining_puzzles_quantity =
+                            (challenge.puzzles_quantity as usize) - proofs.len();
+
+                        let estimated_remaining_time = if hash_rate > 0.0 {
+                            Some(Duration::from_secs_f64(
+                                ((remaining_puzzles_quantity as f64) * expected_attempts_quantity
+                                    / hash_rate)
+                                    .min(u64::MAX as f64),
+                            ))
+                        } else {
+                            None
+                        };
+
+                        let progress = Progress {
+                            attempts_quantity,
+                            solved_puzzles_quantity: proofs.len() as u16,
+                            elapsed,
+                            hash_rate,
+                            estimated_remaining_time,
+                        };
+
+                        // The lagging receiver gets the next progress.
+                        let _ = progress_sender_.try_send(progress);
+                    }
+                }
+            }
+
+            let proof = if challenge.puzzles_quantity == 1 {
+                match proofs.pop() {
+                    Some(proof_) => proof_,
+                    None => {
+                        return Err(Box::from(ErrorKind::Logic));
+                    }
+                }
+            } else {
+                Proof::Multiple(proofs)
+            };
+
+            return match ProofPayload::encode(challenge.algorithm, &proof) {
+                Ok(proof_payload) => Ok(proof_payload),
+                Err(error) => Err(Box::from(error.to_string())),
+            };
+        }
+    }
+
+    // Cancels the solving, which future is dropped.
+    struct DropGuard {
+        cancellation_token: CancellationToken,
+    }
+
+    impl Drop for DropGuard {
+        fn drop<'a>(&'a mut self) -> () {
+            self.cancellation_token.cancel();
+
+            return ();
+        }
+    }
+
+    // SHA256(SHA256(Secret + Nonce)) and the single round SHA256(Secret + Nonce).
+    // The full blocks of the serialized secret are compressed once into the midstate, the last blocks
+    // with the rest of the secret, the padding and the length are prepared once, so, the every attempt
+    // writes the nonce in place and compresses the last blocks only. The compression uses the SHA extensions
+    // of the processor, if they are detected at runtime.
+    #[cfg(any(feature = "double_sha256", feature = "sha256"))]
+    struct Sha256ProofOfWork {
+        algorithm: Algorithm,
+        rounds_quantity: u32,
+        // The secret and the nonce length, for which the blocks are prepared.
+        prepared: Option<(Secret, usize)>,
+        // The serialized secret.
+        buffer: Vec<u8>,
+        midstate: [u32; 8],
+        blocks: Vec<GenericArray<u8, U64>>,
+        nonce_offset: usize,
+        // The block of the next round: the hash of the previous round, the padding and the length.
+        round_block: GenericArray<u8, U64>,
+        result_hash: [u8; 32],
+    }
+
+    #[cfg(any(feature = "double_sha256", feature = "sha256"))]
+    impl Sha256ProofOfWork {
+        const BLOCK_LENGTH: usize = 64;
+        const INITIAL_STATE: [u32; 8] = [
+            0x6a09_e667,
+            0xbb67_ae85,
+            0x3c6e_f372,
+            0xa54f_f53a,
+            0x510e_527f,
+            0x9b05_688c,
+            0x1f83_d9ab,
+            0x5be0_cd19,
+        ];
+
+        fn new(algorithm: Algorithm, rounds_quantity: u32) -> Self {
+            let mut round_block = GenericArray::default();
+
+            round_block[32] = 0x80;
+
+            round_block[(Self::BLOCK_LENGTH - 8)..]
+                .copy_from_slice(256_u64.to_be_bytes().as_slice());
+
+            return Self {
+                algorithm,
+                rounds_quantity,
+                prepared: None,
+                buffer: vec![],
+                midstate: Self::INITIAL_STATE,
+                blocks: vec![],
+                nonce_offset: 0,
+                round_block,
+                result_hash: [0; 32],
+            };
+        }
+
+        fn prepare<'a>(
+            &'a mut self,
+            secret: &'a Secret,
+            nonce_length: usize,
+        ) -> Result<(), Box<dyn Error + 'static>> {
+            if let Some((ref secret_, nonce_length_)) = self.prepared {
+                if secret_ == secret && nonce_length_ == nonce_length {
+                    return Ok(());
+                }
+            }
+
+            self.prepared = None;
+
+            self.buffer.clear();
+
+            Serializer::serialize_(secret, &mut self.buffer)?;
+
+            self.prepare_blocks(nonce_length);
+
+            self.prepared = Some((secret.clone(), nonce_length));
+
+            return Ok(());
+        }
+
+        // Prepares the blocks of the message, that is the buffer followed by the nonce of the given length.
+        fn prepare_blocks<'a>(&'a mut self, nonce_length: usize) -> () {
+            let full_blocks_length = self.buffer.len() - self.buffer.len() % Self::BLOCK_LENGTH;
+
+            self.midstate = Self::INITIAL_STATE;
+
+            for block in self.buffer[..full_blocks_length].chunks_exact(Self::BLOCK_LENGTH) {
+                compress256(
+                    &mut self.midstate,
+                    std::slice::from_ref(GenericArray::from_slice(block)),
+                );
+            }
+
+            let rest_length = self.buffer.len() - full_blocks_length;
+
+            let message_bits_quantity = ((self.buffer.len() + nonce_length) as u64) * 8;
+
+            // The rest, the nonce, the 0x80 byte and the 8 bytes of the length.
+            let blocks_quantity = (rest_length + nonce_length + 9).div_ceil(Self::BLOCK_LENGTH);
+
+            self.blocks.clear();
+
+            self.blocks.resize(blocks_quantity, GenericArray::default());
+
+            self.nonce_offset = rest_length;
+
+            let rest = self.buffer.split_off(full_blocks_length);
+
+            self.write(0, rest.as_slice());
+
+            self.write(rest_length + nonce_length, [0x80].as_slice());
+
+            self.write(
+                blocks_quantity * Self::BLOCK_LENGTH - 8,
+                message_bits_quantity.to_be_bytes().as_slice(),
+            );
+
+            return ();
+        }
+
+        fn write<'a>(&'a mut self, offset: usize, bytes: &'a [u8]) -> () {
+            for (index, byte) in bytes.iter().enumerate() {
+                let offset_ = offset + index;
+
+                self.blocks[offset_ / Self::BLOCK_LENGTH][offset_ % Self::BLOCK_LENGTH] = *byte;
+            }
+
+            return ();
+        }
+
+        fn check_nonce<'a>(&'a mut self, difficulty: Difficulty, nonce: &'a [u8]) -> bool {
+            self.write(self.nonce_offset, nonce);
+
+            let mut state = self.midstate;
+
+            compress256(&mut state, self.blocks.as_slice());
+
+            for _ in 1..self.rounds_quantity {
+                for (index, word) in state.iter().enumerate() {
+                    self.round_block[(index * 4)..(index * 4 + 4)]
+                        .copy_from_slice(word.to_be_bytes().as_slice());
+                }
+
+                state = Self::INITIAL_STATE;
+
+                compress256(&mut state, std::slice::from_ref(&self.round_block));
+            }
+
+            for (index, word) in state.iter().enumerate() {
+                self.result_hash[(index * 4)..(index * 4 + 4)]
+                    .copy_from_slice(word.to_be_bytes().as_slice());
+            }
+
+            return difficulty.is_satisfied_by(self.result_hash.as_slice());
+        }
+    }
+
+    #[cfg(any(feature = "double_sha256", feature = "sha256"))]
+    impl ProofOfWork for Sha256ProofOfWork {
+        fn algorithm<'a>(&'a self) -> Algorithm {
+            return self.algorithm;
+        }
+
+        fn attempt<'a>(
+            &'a mut self,
+            challenge: &'a Challenge,
+            nonce: u64,
+        ) -> Result<Attempt, Box<dyn Error + 'static>> {
+            let nonce_bytes = nonce.to_be_bytes();
+
+            self.prepare(&challenge.secret, nonce_bytes.len())?;
+
+            return Ok(Attempt::nonce(
+                self.check_nonce(challenge.difficulty, nonce_bytes.as_slice()),
+                nonce,
+            ));
+        }
+
+        fn verify<'a>(
+            &'a mut self,
+            challenge: &'a Challenge,
+            proof: &'a Proof,
+        ) -> Result<bool, Box<dyn Error + 'static>> {
+            let nonce = match *proof {
+                Proof::Nonce(ref nonce_) => nonce_,
+                Proof::Solution {
+                    nonce: _,
+                    indices: _,
+                }
+                | Proof::Residue(_)
+                | Proof::Multiple(_) => {
+                    return Ok(false);
+                }
+            };
+
+            if nonce.len() > Proof::MAXIMUM_NONCE_LENGTH {
+                return Ok(false);
+            }
+
+            self.prepare(&challenge.secret, nonce.len())?;
+
+            return Ok(self.check_nonce(challenge.difficulty, nonce.as_slice()));
+        }
+
+        fn describe_cost<'a>(&'a self, difficulty: Difficulty) -> Cost {
+            return Cost {
+                algorithm: self.algorithm,
+                difficulty,
+                hashes_per_attempt_quantity: self.rounds_quantity as u64,
+                memory_per_attempt_bytes_quantity: 0,
+                expected_attempts_quantity: difficulty.expected_attempts_quantity(),
+                expected_hashes_quantity: difficulty.expected_attempts_quantity()
+                    * (self.rounds_quantity as f64),
+            };
+        }
+    }
+
+    // The single or multiple rounds of any digest of the Secret + Nonce.
+    #[cfg(any(feature = "sha3_256", feature = "blake3"))]
+    struct HashProofOfWork<D>
+    where
+        D: Digest + Send,
+    {
+        algorithm: Algorithm,
+        digest: D,
+        rounds_quantity: u32,
+        result_hash: Vec<u8>,
+        // The serialized secret of the last challenge followed by the nonce.
+        buffer: Vec<u8>,
+        secret: Option<Secret>,
+        secret_length: usize,
+    }
+
+    #[cfg(any(feature = "sha3_256", feature = "blake3"))]
+    impl<D> HashProofOfWork<D>
+    where
+        D: Digest + Send,
+    {
+        fn new(algorithm: Algorithm, digest: D, rounds_quantity: u32) -> Self {
+            let bytes_quantity = digest.output_bytes();
+
+            return Self {
+                algorithm,
+                digest,
+                rounds_quantity,
+                result_hash: vec![0; bytes_quantity],
+                buffer: vec![],
+                secret: None,
+                secret_length: 0,
+            };
+        }
+
+        fn prepare<'a>(&'a mut self, secret: &'a Secret) -> Result<(), Box<dyn Error + 'static>> {
+            if self.secret.as_ref() == Some(secret) {
+                return Ok(());
+            }
+
+            self.secret = None;
+
+            self.buffer.clear();
+
+            Serializer::serialize_(secret, &mut self.buffer)?;
+
+            self.secret = Some(secret.clone());
+
+            self.secret_length = self.buffer.len();
+
+            return Ok(());
+        }
+
+        fn check_nonce<'a>(&'a mut self, difficulty: Difficulty, nonce: &'a [u8]) -> bool {
+            self.buffer.truncate(self.secret_length);
+
+            self.buffer.extend_from_slice(nonce);
+
+            self.digest.reset();
+
+            self.digest.input(self.buffer.as_slice());
+
+            self.digest.result(self.result_hash.as_mut_slice());
+
+            for _ in 1..self.rounds_quantity {
+                self.digest.reset();
+
+                self.digest.input(self.result_hash.as_slice());
+
+                self.digest.result(self.result_hash.as_mut_slice());
+            }
+
+            return difficulty.is_satisfied_by(self.result_hash.as_slice());
+        }
+    }
+
+    #[cfg(any(feature = "sha3_256", feature = "blake3"))]
+    impl<D> ProofOfWork for HashProofOfWork<D>
+    where
+        D: Digest + Send,
+    {
+        fn algorithm<'a>(&'a self) -> Algorithm {
+            return self.algorithm;
+        }
+
+        fn attempt<'a>(
+            &'a mut self,
+            challenge: &'a Challenge,
+            nonce: u64,
+        ) -> Result<Attempt, Box<dyn Error + 'static>> {
+            self.prepare(&challenge.secret)?;
+
+            return Ok(Attempt::nonce(
+                self.check_nonce(challenge.difficulty, nonce.to_be_bytes().as_slice()),
+                nonce,
+            ));
+        }
+
+        fn verify<'a>(
+            &'a mut self,
+            challenge: &'a Challenge,
+            proof: &'a Proof,
+        ) -> Result<bool, Box<dyn Error + 'static>> {
+            let nonce = match *proof {
+                Proof::Nonce(ref nonce_) => nonce_,
+                Proof::Solution {
+                    nonce: _,
+                    indices: _,
+                }
+                | Proof::Residue(_)
+                | Proof::Multiple(_) => {
+                    return Ok(false);
+                }
+            };
+
+            self.prepare(&challenge.secret)?;
+
+            if nonce.len() > Proof::MAXIMUM_NONCE_LENGTH {
+                return Ok(false);
+            }
+
+            return Ok(self.check_nonce(challenge.difficulty, nonce.as_slice()));
+        }
+
+        fn describe_cost<'a>(&'a self, difficulty: Difficulty) -> Cost {
+            return Cost {
+                algorithm: self.algorithm,
+                difficulty,
+                hashes_per_attempt_quantity: self.rounds_quantity as u64,
+                memory_per_attempt_bytes_quantity: 0,
+                expected_attempts_quantity: difficulty.expected_attempts_quantity(),
+                expected_hashes_quantity: difficulty.expected_attempts_quantity()
+                    * (self.rounds_quantity as f64),
+            };
+        }
+    }
+
+    // Adapts BLAKE3 to the rust-crypto digest interface.
+    #[cfg(feature = "blake3")]
+    struct Blake3 {
+        hasher: Blake3Hasher,
+    }
+
+    #[cfg(feature = "blake3")]
+    impl Blake3 {
+        fn new() -> Self {
+            return Self {
+                hasher: Blake3Hasher::new(),
+            };
+        }
+    }
+
+    #[cfg(feature = "blake3")]
+    impl Digest for Blake3 {
+        fn input<'a>(&'a mut self, input: &'a [u8]) -> () {
+            self.hasher.update(input);
+
+            return ();
+        }
+
+        fn result<'a>(&'a mut self, out: &'a mut [u8]) -> () {
+            out.copy_from_slice(self.hasher.finalize().as_bytes());
+
+            return ();
+        }
+
+        fn reset<'a>(&'a mut self) -> () {
+            self.hasher.reset();
+
+            return ();
+        }
+
+        fn output_bits<'a>(&'a self) -> usize {
+            return blake3::OUT_LEN * 8;
+        }
+
+        fn block_size<'a>(&'a self) -> usize {
+            return blake3::BLOCK_LEN;
+        }
+    }
+
+    // Argon2id(Nonce, Secret) with the parameters of the challenge. Every attempt is a single memory-hard hash,
+    // so, the difficulty is expected to be much lower, than the difficulty for the plain hashes.
+    #[cfg(feature = "argon2id")]
+    struct Argon2idProofOfWork {
+        parameters: MemoryHardParameters,
+        result_hash: [u8; Self::OUTPUT_LENGTH],
+        // The serialized secret of the last challenge, that is used as the salt.
+        salt: Vec<u8>,
+        prepared: Option<(Secret, MemoryHardParameters)>,
+        argon2: Option<Argon2<'static>>,
+        memory: Vec<Argon2Block>,
+    }
+
+    #[cfg(feature = "argon2id")]
+    impl Argon2idProofOfWork {
+        const OUTPUT_LENGTH: usize = 32;
+
+        fn new(parameters: MemoryHardParameters) -> Self {
+            return Self {
+                parameters,
+                result_hash: [0; Self::OUTPUT_LENGTH],
+                salt: vec![],
+                prepared: None,
+                argon2: None,
+                memory: vec![],
+            };
+        }
+
+        fn prepare<'a>(
+            &'a mut self,
+            challenge: &'a Challenge,
+        ) -> Result<(), Box<dyn Error + 'static>> {
+            let parameters = match challenge.parameters {
+                Parameters::MemoryHard(parameters_) => parameters_,
+                Parameters::None
+                | Parameters::GeneralizedBirthday(_)
+                | Parameters::SequentialSquaring(_) => {
+                    return Err(Box::from(ErrorKind::Logic));
+                }
+            };
+
+            if let Some((ref secret, ref parameters_)) = self.prepared {
+                if *secret == challenge.secret && *parameters_ == parameters {
+                    return Ok(());
+                }
+            }
+
+            parameters.validate()?;
+
+            self.prepared = None;
+
+            let argon2_parameters = match Argon2Params::new(
+                parameters.memory_cost,
+                parameters.time_cost,
+                parameters.parallelism,
+                Some(Self::OUTPUT_LENGTH),
+            ) {
+                Ok(argon2_parameters_) => argon2_parameters_,
+                Err(error) => {
+                    return Err(Box::from(error));
+                }
+            };
+
+            self.memory
+                .resize(argon2_parameters.block_count(), Argon2Block::default());
+
+            self.argon2 = Some(Argon2::new(
+                Argon2Algorithm::Argon2id,
+                Argon2Version::V0x13,
+                argon2_parameters,
+            ));
+
+            self.salt.clear();
+
+            Serializer::serialize_(&challenge.secret, &mut self.salt)?;
+
+            self.prepared = Some((challenge.secret.clone(), parameters));
+
+            return Ok(());
+        }
+
+        fn check_nonce<'a>(
+            &'a mut self,
+            difficulty: Difficulty,
+            nonce: &'a [u8],
+        ) -> Result<bool, Box<dyn Error + 'static>> {
+            let argon2 = match self.argon2 {
+                Some(ref argon2_) => argon2_,
+                None => {
+                    return Err(Box::from(ErrorKind::Logic));
+                }
+            };
+
+            if let Err(error) = argon2.hash_password_into_with_memory(
+                nonce,
+                self.salt.as_slice(),
+                self.result_hash.as_mut_slice(),
+                self.memory.as_mut_slice(),
+            ) {
+                return Err(Box::from(error));
+            }
+
+            return Ok(difficulty.is_satisfied_by(self.result_hash.as_slice()));
+        }
+    }
+
+    #[cfg(feature = "argon2id")]
+    impl ProofOfWork for Argon2idProofOfWork {
+        fn algorithm<'a>(&'a self) -> Algorithm {
+            return Algorithm::Argon2id;
+        }
+
+        fn generate_challenge<'a>(&'a self, difficulty: Difficulty) -> Challenge {
+            return Challenge {
+                secret: Secret {
+                    value: Uuid::new_v4(),
+                },
+                difficulty,
+                algorithm: Algorithm::Argon2id,
+                parameters: Parameters::MemoryHard(self.parameters),
+                puzzles_quantity: 1,
+            };
+        }
+
+        fn attempt<'a>(
+            &'a mut self,
+            challenge: &'a Challenge,
+            nonce: u64,
+        ) -> Result<Attempt, Box<dyn Error + 'static>> {
+            self.prepare(challenge)?;
+
+            return Ok(Attempt::nonce(
+                self.check_nonce(challenge.difficulty, nonce.to_be_bytes().as_slice())?,
+                nonce,
+            ));
+        }
+
+        fn verify<'a>(
+            &'a mut self,
+            challenge: &'a Challenge,
+            proof: &'a Proof,
+        ) -> Result<bool, Box<dyn Error + 'static>> {
+            let nonce = match *proof {
+                Proof::Nonce(ref nonce_) => nonce_,
+                Proof::Solution {
+                    nonce: _,
+                    indices: _,
+                }
+                | Proof::Residue(_)
+                | Proof::Multiple(_) => {
+                    return Ok(false);
+                }
+            };
+
+            self.prepare(challenge)?;
+
+            if nonce.len() > Proof::MAXIMUM_NONCE_LENGTH {
+                return Ok(false);
+            }
+
+            return self.check_nonce(challenge.difficulty, nonce.as_slice());
+        }
+
+        fn describe_cost<'a>(&'a self, difficulty: Difficulty) -> Cost {
+            return Cost {
+                algorithm: Algorithm::Argon2id,
+                difficulty,
+                hashes_per_attempt_quantity: 1,
+                memory_per_attempt_bytes_quantity: (self.parameters.memory_cost as u64) * 1024,
+                expected_attempts_quantity: difficulty.expected_attempts_quantity(),
+                expected_hashes_quantity: difficulty.expected_attempts_quantity(),
+            };
+        }
+    }
+
+    // The generalized birthday problem (Equihash): find 2^k distinct indices, which leaf hashes of n bits
+    // XOR to zero, by the Wagner algorithm with k rounds of the collisions on n/(k+1) bits.
+    // The solution needs the memory for all of the 2^(n/(k+1)+1) leaves, the verification needs 2^k hashes only.
+    // Every solution is an attempt, that also has to satisfy the difficulty.
+    #[cfg(feature = "equihash")]
+    struct EquihashProofOfWork {
+        parameters: GeneralizedBirthdayParameters,
+        digest: Sha256,
+        result_hash: [u8; Self::OUTPUT_LENGTH],
+        // The serialized secret of the last challenge followed by the nonce and the indices.
+        buffer: Vec<u8>,
+        secret: Option<Secret>,
+        secret_length: usize,
+        // The nodes of the every round of the Wagner algorithm, are kept between the attempts.
+        levels: Vec<Vec<EquihashNode>>,
+    }
+
+    #[cfg(feature = "equihash")]
+    #[derive(Clone, Copy)]
+    struct EquihashNode {
+        hash: u128,
+        // The leftmost leaf index of the subtree.
+        first_index: u32,
+        // The positions of the children on the previous level.
+        left: u32,
+        right: u32,
+    }
+
+    #[cfg(feature = "equihash")]
+    impl EquihashProofOfWork {
+        const OUTPUT_LENGTH: usize = 32;
+
+        fn new(parameters: GeneralizedBirthdayParameters) -> Self {
+            return Self {
+                parameters,
+                digest: Sha256::new(),
+                result_hash: [0; Self::OUTPUT_LENGTH],
+                buffer: vec![],
+                secret: None,
+                secret_length: 0,
+                levels: vec![],
+            };
+        }
+
+        fn prepare<'a>(
+            &'a mut self,
+            challenge: &'a Challenge,
+        ) -> Result<GeneralizedBirthdayParameters, Box<dyn Error + 'static>> {
+            let parameters = match challenge.parameters {
+                Parameters::GeneralizedBirthday(parameters_) => parameters_,
+                Parameters::None
+                | Parameters::MemoryHard(_)
+                | Parameters::SequentialSquaring(_) => {
+                    return Err(Box::from(ErrorKind::Logic));
+                }
+            };
+
+            parameters.validate()?;
+
+            if self.secret.as_ref() == Some(&challenge.secret) {
+                return Ok(parameters);
+            }
+
+            self.secret = None;
+
+            self.buffer.clear();
+
+            Serializer::serialize_(&challenge.secret, &mut self.buffer)?;
+
+            self.secret = Some(challenge.secret.clone());
+
+            self.secret_length = self.buffer.len();
+
+            return Ok(parameters);
+        }
+
+        fn hash<'a>(&'a mut self, nonce: &'a [u8], indices: &'a [u32]) -> () {
+            self.buffer.truncate(self.secret_length);
+
+            self.buffer.extend_from_slice(nonce);
+
+            for index in indices.iter() {
+                self.buffer
+                    .extend_from_slice(index.to_be_bytes().as_slice());
+            }
+
+            self.digest.reset();
+
+            self.digest.input(self.buffer.as_slice());
+
+            self.digest.result(self.result_hash.as_mut_slice());
+
+            return ();
+        }
+
+        // The first n bits of SHA256(Secret + Nonce + Index).
+        fn leaf_hash<'a>(
+            &'a mut self,
+            parameters: GeneralizedBirthdayParameters,
+            nonce: &'a [u8],
+            index: u32,
+        ) -> u128 {
+            self.hash(nonce, [index].as_slice());
+
+            let mut bytes = [0; 16];
+
+            bytes.copy_from_slice(&self.result_hash[..16]);
+
+            return u128::from_be_bytes(bytes) >> (128 - parameters.bits_quantity);
+        }
+
+        // SHA256(Secret + Nonce + Indices) should satisfy the difficulty.
+        fn is_satisfied<'a>(
+            &'a mut self,
+            difficulty: Difficulty,
+            nonce: &'a [u8],
+            indices: &'a [u32],
+        ) -> bool {
+            self.hash(nonce, indices);
+
+            return difficulty.is_satisfied_by(self.result_hash.as_slice());
+        }
+
+        fn expand<'a>(
+            levels: &'a [Vec<EquihashNode>],
+            round: usize,
+            position: u32,
+            indices: &'a mut Vec<u32>,
+        ) -> () {
+            let node = levels[round][position as usize];
+
+            if round == 0 {
+                indices.push(node.first_index);
+            } else {
+                Self::expand(levels, round - 1, node.left, indices);
+
+                Self::expand(levels, round - 1, node.right, indices);
+            }
+
+            return ();
+        }
+
+        fn is_distinct<'a>(indices: &'a [u32]) -> bool {
+            let mut indices_ = indices.to_vec();
+
+            indices_.sort_unstable();
+
+            indices_.dedup();
+
+            return indices_.len() == indices.len();
+        }
+    }
+
+    #[cfg(feature = "equihash")]
+    impl ProofOfWork for EquihashProofOfWork {
+        fn algorithm<'a>(&'a self) -> Algorithm {
+            return Algorithm::Equihash;
+        }
+
+        fn generate_challenge<'a>(&'a self, difficulty: Difficulty) -> Challenge {
+            return Challenge {
+                secret: Secret {
+                    value: Uuid::new_v4(),
+                },
+                difficulty,
+                algorithm: Algorithm::Equihash,
+                parameters: Parameters::GeneralizedBirthday(self.parameters),
+                puzzles_quantity: 1,
+            };
+        }
+
+        fn attempt<'a>(
+            &'a mut self,
+            challenge: &'a Challenge,
+            nonce: u64,
+        ) -> Result<Attempt, Box<dyn Error + 'static>> {
+            let parameters = self.prepare(challenge)?;
+
+            let nonce_bytes = nonce.to_be_bytes();
+
+            let rounds_quantity = parameters.rounds_quantity as usize;
+
+            let leaves_quantity = parameters.leaves_quantity();
+
+            let mut levels = std::mem::take(&mut self.levels);
+
+            levels.resize_with(rounds_quantity + 1, Vec::new);
+
+            for level in levels.iter_mut() {
+                level.clear();
+            }
+
+            for index in 0..leaves_quantity {
+                let hash = self.leaf_hash(parameters, nonce_bytes.as_slice(), index);
+
+                levels[0].push(EquihashNode {
+                    hash,
+                    first_index: index,
+                    left: index,
+                    right: index,
+                });
+            }
+
+            for round in 1..=rounds_quantity {
+                let (previous_levels, next_levels) = levels.split_at_mut(round);
+
+                let previous_level = &mut previous_levels[round - 1];
+
+                let level = &mut next_levels[0];
+
+                let collision_key =
+                    |node: &EquihashNode| parameters.collision_key(round as u32, node.hash);
+
+                previous_level.sort_unstable_by_key(collision_key);
+
+                let mut start: usize = 0;
+
+                'a: while start < previous_level.len() {
+                    let key = collision_key(&previous_level[start]);
+
+                    let mut end = start + 1;
+
+                    while end < previous_level.len() && collision_key(&previous_level[end]) == key {
+                        end += 1;
+                    }
+
+                    for left in start..end {
+                        for right in (left + 1)..end {
+                            let (left_node, right_node) =
+                                (previous_level[left], previous_level[right]);
+
+                            let hash = left_node.hash ^ right_node.hash;
+
+                            // The equal intermediate hashes lead to the repeated indices only.
+                            if left_node.first_index == right_node.first_index
+                                || (round < rounds_quantity && hash == 0)
+                            {
+                                continue;
+                            }
+
+                            let (left_, right_) = if left_node.first_index < right_node.first_index
+                            {
+                                (left, right)
+                            } else {
+                                (right, left)
+                            };
+
+                            level.push(EquihashNode {
+                                hash,
+                                first_index: left_node.first_index.min(right_node.first_index),
+                                left: left_ as u32,
+                                right: right_ as u32,
+                            });
+
+                            if level.len() >= 2 * (leaves_quantity as usize) {
+                                break 'a;
+                            }
+                        }
+                    }
+
+                    start = end;
+                }
+            }
+
+            let mut attempts_quantity: u64 = 0;
+
+            let mut proof: Option<Proof> = None;
+
+            let mut indices: Vec<u32> = vec![];
+
+            'b: for position in 0..levels[rounds_quantity].len() {
+                indices.clear();
+
+                Self::expand(
+                    levels.as_slice(),
+                    rounds_quantity,
+                    position as u32,
+                    &mut indices,
+                );
+
+                if !Self::is_distinct(indices.as_slice()) {
+                    continue 'b;
+                }
+
+                attempts_quantity += 1;
+
+                if self.is_satisfied(
+                    challenge.difficulty,
+                    nonce_bytes.as_slice(),
+                    indices.as_slice(),
+                ) {
+                    proof = Some(Proof::Solution {
+                        nonce: nonce_bytes.to_vec(),
+                        indices,
+                    });
+
+                    break 'b;
+                }
+            }
+
+            self.levels = levels;
+
+            return Ok(Attempt {
+                attempts_quantity,
+                proof,
+            });
+        }
+
+        fn verify<'a>(
+            &'a mut self,
+            challenge: &'a Challenge,
+            proof: &'a Proof,
+        ) -> Result<bool, Box<dyn Error + 'static>> {
+            let (nonce, indices) = match *proof {
+                Proof::Solution {
+                    ref nonce,
+                    ref indices,
+                } => (nonce.as_slice(), indices),
+                Proof::Nonce(_) | Proof::Residue(_) | Proof::Multiple(_) => {
+                    return Ok(false);
+                }
+            };
+
+            let parameters = self.prepare(challenge)?;
+
+            let leaves_quantity = parameters.leaves_quantity();
+
+            if nonce.len() > Proof::MAXIMUM_NONCE_LENGTH
+                || indices.len() != parameters.solution_length()
+                || indices.iter().any(|index| *index >= leaves_quantity)
+                || !Self::is_distinct(indices.as_slice())
+            {
+                return Ok(false);
+            }
+
+            let mut nodes: Vec<(u128, u32)> = Vec::with_capacity(indices.len());
+
+            for index in indices.iter() {
+                nodes.push((self.leaf_hash(parameters, nonce, *index), *index));
+            }
+
+            for round in 1..=parameters.rounds_quantity {
+                let mut nodes_: Vec<(u128, u32)> = Vec::with_capacity(nodes.len() / 2);
+
+                for pair in nodes.chunks_exact(2) {
+                    let ((left_hash, left_first_index), (right_hash, right_first_index)) =
+                        (pair[0], pair[1]);
+
+                    let hash = left_hash ^ right_hash;
+
+                    if left_first_index >= right_first_index
+                        || parameters.collision_key(round, hash) != 0
+                    {
+                        return Ok(false);
+                    }
+
+                    nodes_.push((hash, left_first_index));
+                }
+
+                nodes = nodes_;
+            }
+
+            return Ok(self.is_satisfied(challenge.difficulty, nonce, indices.as_slice()));
+        }
+
+        fn describe_cost<'a>(&'a self, difficulty: Difficulty) -> Cost {
+            let leaves_quantity = self.parameters.leaves_quantity();
+
+            let hashes_per_attempt_quantity = (leaves_quantity
+                / GeneralizedBirthdayParameters::EXPECTED_SOLUTIONS_QUANTITY)
+                as u64;
+
+            return Cost {
+                algorithm: Algorithm::Equihash,
+                difficulty,
+                hashes_per_attempt_quantity,
+                memory_per_attempt_bytes_quantity: self.parameters.memory_bytes_quantity(),
+                expected_attempts_quantity: difficulty.expected_attempts_quantity(),
+                expected_hashes_quantity: difficulty.expected_attempts_quantity()
+                    * (hashes_per_attempt_quantity as f64),
+            };
+        }
+    }
+
+    // The time-lock puzzle of Rivest, Shamir and Wagner: X^(2^T) mod N, where X is derived from the secret.
+    // The squarings are sequential, so, the parallel hardware does not shorten the solve time. The server knows
+    // the Euler totient of N and verifies the solution with the single modular exponentiation by 2^T mod totient.
+    // The difficulty is not applied, the work is defined by the T.
+    #[cfg(feature = "time_lock")]
+    struct TimeLockProofOfWork {
+        parameters: SequentialSquaringParameters,
+        digest: Sha256,
+        result_hash: [u8; Self::OUTPUT_LENGTH],
+        buffer: Vec<u8>,
+    }
+
+    #[cfg(feature = "time_lock")]
+    impl TimeLockProofOfWork {
+        const OUTPUT_LENGTH: usize = 32;
+
+        fn new(parameters: SequentialSquaringParameters) -> Self {
+            return Self {
+                parameters,
+                digest: Sha256::new(),
+                result_hash: [0; Self::OUTPUT_LENGTH],
+                buffer: vec![],
+            };
+        }
+
+        // The modulus and the base of the puzzle.
+        fn prepare<'a>(
+            &'a mut self,
+            challenge: &'a Challenge,
+        ) -> Result<(&'a SequentialSquaringParameters, BigUint, BigUint), Box<dyn Error + 'static>>
+        {
+            let parameters = match challenge.parameters {
+                Parameters::SequentialSquaring(ref parameters_) => parameters_,
+                Parameters::None
+                | Parameters::MemoryHard(_)
+                | Parameters::GeneralizedBirthday(_) => {
+                    return Err(Box::from(ErrorKind::Logic));
+                }
+            };
+
+            parameters.validate()?;
+
+            let modulus = BigUint::from_bytes_be(parameters.modulus.as_slice());
+
+            self.buffer.clear();
+
+            Serializer::serialize_(&challenge.secret, &mut self.buffer)?;
+
+            self.digest.reset();
+
+            self.digest.input(self.buffer.as_slice());
+
+            self.digest.result(self.result_hash.as_mut_slice());
+
+            let base = BigUint::from_bytes_be(self.result_hash.as_slice()) % &modulus;
+
+            return Ok((parameters, modulus, base));
+        }
+
+        fn square<'a>(base: &'a BigUint, modulus: &'a BigUint, squarings_quantity: u64) -> BigUint {
+            let mut residue = base.clone();
+
+            for _ in 0..squarings_quantity {
+                residue = (&residue * &residue) % modulus;
+            }
+
+            return residue;
+        }
+    }
+
+    #[cfg(feature = "time_lock")]
+    impl ProofOfWork for TimeLockProofOfWork {
+        fn algorithm<'a>(&'a self) -> Algorithm {
+            return Algorithm::TimeLock;
+        }
+
+        fn generate_challenge<'a>(&'a self, difficulty: Difficulty) -> Challenge {
+            return Challenge {
+                secret: Secret {
+                    value: Uuid::new_v4(),
+                },
+                difficulty,
+                algorithm: Algorithm::TimeLock,
+                parameters: Parameters::SequentialSquaring(self.parameters.clone()),
+                puzzles_quantity: 1,
+            };
+        }
+
+        // The nonce is not used, the single attempt always succeeds.
+        fn attempt<'a>(
+            &'a mut self,
+            challenge: &'a Challenge,
+            _nonce: u64,
+        ) -> Result<Attempt, Box<dyn Error + 'static>> {
+            let (parameters, modulus, base) = self.prepare(challenge)?;
+
+            let residue = Self::square(&base, &modulus, parameters.squarings_quantity);
+
+            return Ok(Attempt {
+                attempts_quantity: 1,
+                proof: Some(Proof::Residue(residue.to_bytes_be())),
+            });
+        }
+
+        fn verify<'a>(
+            &'a mut self,
+            challenge: &'a Challenge,
+            proof: &'a Proof,
+        ) -> Result<bool, Box<dyn Error + 'static>> {
+            let residue = match *proof {
+                Proof::Residue(ref residue_) => BigUint::from_bytes_be(residue_.as_slice()),
+                Proof::Nonce(_)
+                | Proof::Solution {
+                    nonce: _,
+                    indices: _,
+                }
+                | Proof::Multiple(_) => {
+                    return Ok(false);
+                }
+            };
+
+            let (parameters, modulus, base) = self.prepare(challenge)?;
+
+            if residue >= modulus {
+                return Ok(false);
+            }
+
+            // Without the trapdoor the verification is as expensive as the solution.
+            let residue_ = match parameters.trapdoor {
+                Some(ref trapdoor) => {
+                    let exponent = BigUint::from(2_u32).modpow(
+                        &BigUint::from(parameters.squarings_quantity),
+                        &BigUint::from_bytes_be(trapdoor.totient.as_slice()),
+                    );
+
+                    base.modpow(&exponent, &modulus)
+                }
+                None => Self::square(&base, &modulus, parameters.squarings_quantity),
+            };
+
+            return Ok(residue == residue_);
+        }
+
+        fn describe_cost<'a>(&'a self, difficulty: Difficulty) -> Cost {
+            return Cost {
+                algorithm: Algorithm::TimeLock,
+                difficulty,
+                hashes_per_attempt_quantity: self.parameters.squarings_quantity,
+                memory_per_attempt_bytes_quantity: 0,
+                expected_attempts_quantity: 1.0,
+                expected_hashes_quantity: self.parameters.squarings_quantity as f64,
+            };
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    pub enum Algorithm {
+        DoubleSha256,
+        Sha256,
+        Sha3_256,
+        Blake3,
+        Argon2id,
+        Equihash,
+        TimeLock,
+    }
+
+    impl Algorithm {
+        pub const ALL: [Self; 7] = [
+            Self::DoubleSha256,
+            Self::Sha256,
+            Self::Sha3_256,
+            Self::Blake3,
+            Self::Argon2id,
+            Self::Equihash,
+            Self::TimeLock,
+        ];
+
+        pub fn name<'a>(&'a self) -> &'static str {
+            return match *self {
+                Self::DoubleSha256 => "double_sha256",
+                Self::Sha256 => "sha256",
+                Self::Sha3_256 => "sha3_256",
+                Self::Blake3 => "blake3",
+                Self::Argon2id => "argon2id",
+                Self::Equihash => "equihash",
+                Self::TimeLock => "time_lock",
+            };
+        }
+
+        // The maximum length of the encoded proof of the single puzzle with the maximum parameters.
+        pub fn maximum_proof_length<'a>(&'a self) -> usize {
+            return match *self {
+                Self::DoubleSha256
+                | Self::Sha256
+                | Self::Sha3_256
+                | Self::Blake3
+                | Self::Argon2id => 64,
+                // 2^9 indices, every of which takes up to 5 bytes.
+                Self::Equihash => 4096,
+                // The 4096 bits residue.
+                Self::TimeLock => 1024,
+            };
+        }
+    }
+
+    impl FromStr for Algorithm {
+        type Err = ErrorKind;
+
+        fn from_str(name: &str) -> Result<Self, Self::Err> {
+            for algorithm in Self::ALL.iter() {
+                if algorithm.name() == name {
+                    return Ok(*algorithm);
+                }
+            }
+
+            return Err(ErrorKind::Logic);
+        }
+    }
+
+    impl Display for Algorithm {
+        fn fmt<'a>(&'a self, formatter: &'a mut Formatter<'_>) -> FmtResult {
+            write!(formatter, "{}", self.name())
+        }
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct Challenge {
+        pub secret: Secret,
+        pub difficulty: Difficulty,
+        pub algorithm: Algorithm,
+        #[serde(default)]
+        pub parameters: Parameters,
+        // The quantity of the distinct solutions, that are required.
+        #[serde(default = "Challenge::default_puzzles_quantity")]
+        pub puzzles_quantity: u16,
+    }
+
+    impl Challenge {
+        // Protects the client from the challenges, that can not be solved in the reasonable time.
+        pub const MAXIMUM_PUZZLES_QUANTITY: u16 = 256;
+
+        fn default_puzzles_quantity() -> u16 {
+            return 1;
+        }
+    }
+
+    // The algorithm specific parameters, that are announced in the challenge.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum Parameters {
+        None,
+        MemoryHard(MemoryHardParameters),
+        GeneralizedBirthday(GeneralizedBirthdayParameters),
+        SequentialSquaring(SequentialSquaringParameters),
+    }
+
+    impl Parameters {
+        pub fn default_for(algorithm: Algorithm) -> Self {
+            return match algorithm {
+                Algorithm::Argon2id => Self::MemoryHard(MemoryHardParameters::DEFAULT),
+                Algorithm::Equihash => {
+                    Self::GeneralizedBirthday(GeneralizedBirthdayParameters::DEFAULT)
+                }
+                Algorithm::TimeLock => {
+                    Self::SequentialSquaring(SequentialSquaringParameters::unkeyed(
+                        SequentialSquaringParameters::DEFAULT_SQUARINGS_QUANTITY,
+                    ))
+                }
+                _ => Self::None,
+            };
+        }
+
+        pub fn is_compatible_with(&self, algorithm: Algorithm) -> bool {
+            return match algorithm {
+                Algorithm::Argon2id => matches!(*self, Self::MemoryHard(_)),
+                Algorithm::Equihash => matches!(*self, Self::GeneralizedBirthday(_)),
+                Algorithm::TimeLock => matches!(*self, Self::SequentialSquaring(_)),
+                _ => *self == Self::None,
+            };
+        }
+    }
+
+    impl Default for Parameters {
+        fn default() -> Self {
+            return Self::None;
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct MemoryHardParameters {
+        // In KiB.
+        pub memory_cost: u32,
+        // The quantity of passes over the memory.
+        pub time_cost: u32,
+        // The quantity of lanes.
+        pub parallelism: u32,
+    }
+
+    impl MemoryHardParameters {
+        // Allows the server to verify thousands of attempts per second.
+        pub const DEFAULT: Self = Self {
+            memory_cost: 256,
+            time_cost: 1,
+            parallelism: 1,
+        };
+        // Protects the client from the parameters, that exhaust its memory.
+        pub const MAXIMUM_MEMORY_COST: u32 = 1024 * 1024;
+        pub const MAXIMUM_TIME_COST: u32 = 64;
+        pub const MAXIMUM_PARALLELISM: u32 = 64;
+
+        pub fn validate<'a>(&'a self) -> Result<(), Box<dyn Error + 'static>> {
+            if self.parallelism == 0
+                || self.parallelism > Self::MAXIMUM_PARALLELISM
+                || self.time_cost == 0
+                || self.time_cost > Self::MAXIMUM_TIME_COST
+                || self.memory_cost < 8 * self.parallelism
+                || self.memory_cost > Self::MAXIMUM_MEMORY_COST
+            {
+                return Err(Box::from(ErrorKind::Logic));
+            }
+
+            return Ok(());
+        }
+    }
+
+    // The n and the k of the generalized birthday problem.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct GeneralizedBirthdayParameters {
+        // The n, the quantity of bits of the every leaf hash.
+        pub bits_quantity: u32,
+        // The k, the solution consists of 2^k indices.
+        pub rounds_quantity: u32,
+    }
+
+    impl GeneralizedBirthdayParameters {
+        // At most 44 MiB of the memory for the solver and 32 hashes for the verifier.
+        pub const DEFAULT: Self = Self {
+            bits_quantity: 96,
+            rounds_quantity: 5,
+        };
+        // The collision bits and the rounds are bounded separately only for the arithmetic,
+        // the memory bound protects the client from the parameters, that exhaust its memory.
+        pub const MAXIMUM_COLLISION_BITS_QUANTITY: u32 = 20;
+        pub const MAXIMUM_ROUNDS_QUANTITY: u32 = 9;
+        pub const MAXIMUM_MEMORY_BYTES_QUANTITY: u64 = 256 * 1024 * 1024;
+        // The size of the node of the Wagner algorithm.
+        const NODE_BYTES_QUANTITY: u64 = 32;
+        // The quantity of the solutions, that the Wagner algorithm finds on average.
+        #[cfg(feature = "equihash")]
+        const EXPECTED_SOLUTIONS_QUANTITY: u32 = 2;
+
+        pub fn validate<'a>(&'a self) -> Result<(), Box<dyn Error + 'static>> {
+            if self.rounds_quantity == 0
+                || self.rounds_quantity > Self::MAXIMUM_ROUNDS_QUANTITY
+                || self.bits_quantity > 128
+                || self.collision_bits_quantity() * (self.rounds_quantity + 1) != self.bits_quantity
+                || self.collision_bits_quantity() == 0
+                || self.collision_bits_quantity() > Self::MAXIMUM_COLLISION_BITS_QUANTITY
+                || self.memory_bytes_quantity() > Self::MAXIMUM_MEMORY_BYTES_QUANTITY
+            {
+                return Err(Box::from(ErrorKind::Logic));
+            }
+
+            return Ok(());
+        }
+
+        // The solver keeps all of the leaves and at most twice as many nodes for every round.
+        pub fn memory_bytes_quantity<'a>(&'a self) -> u64 {
+            return (self.leaves_quantity() as u64)
+                * (2 * (self.rounds_quantity as u64) + 1)
+                * Self::NODE_BYTES_QUANTITY;
+        }
+
+        pub fn collision_bits_quantity<'a>(&'a self) -> u32 {
+            return self.bits_quantity / (self.rounds_quantity + 1);
+        }
+
+        pub fn leaves_quantity<'a>(&'a self) -> u32 {
+            return 1 << (self.collision_bits_quantity() + 1);
+        }
+
+        pub fn solution_length<'a>(&'a self) -> usize {
+            return 1 << self.rounds_quantity;
+        }
+
+        // The bits, that should collide after the round. The last round requires the whole hash to be zero.
+        #[cfg(feature = "equihash")]
+        fn collision_key<'a>(&'a self, round: u32, hash: u128) -> u128 {
+            if round == self.rounds_quantity {
+                return hash;
+            }
+
+            return hash >> (self.bits_quantity - round * self.collision_bits_quantity());
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct SequentialSquaringParameters {
+        // The RSA modulus N, big-endian.
+        pub modulus: Vec<u8>,
+        // The T.
+        pub squarings_quantity: u64,
+        // Is known to the server only and is never sent.
+        #[serde(skip)]
+        pub trapdoor: Option<Trapdoor>,
+    }
+
+    impl SequentialSquaringParameters {
+        pub const DEFAULT_MODULUS_BITS_QUANTITY: u32 = 2048;
+        // About ten seconds for the 2048 bits modulus on the modern machine.
+        pub const DEFAULT_SQUARINGS_QUANTITY: u64 = 1 << 20;
+        pub const MINIMUM_MODULUS_BITS_QUANTITY: u32 = 512;
+        pub const MAXIMUM_MODULUS_BITS_QUANTITY: u32 = 4096;
+        // Protects the client from the puzzles, that take days.
+        pub const MAXIMUM_SQUARINGS_QUANTITY: u64 = 1 << 32;
+
+        // Without the modulus, so, is suitable for the solution and the verification of the challenges only.
+        pub fn unkeyed(squarings_quantity: u64) -> Self {
+            return Self {
+                modulus: vec![],
+                squarings_quantity,
+                trapdoor: None,
+            };
+        }
+
+        // Generates the modulus of the two random primes. Is expensive, so, the modulus is reused
+        // for all of the challenges, every challenge has its own base.
+        #[cfg(feature = "time_lock")]
+        pub fn generate(
+            modulus_bits_quantity: u32,
+            squarings_quantity: u64,
+        ) -> Result<Self, Box<dyn Error + 'static>> {
+            if !(Self::MINIMUM_MODULUS_BITS_QUANTITY..=Self::MAXIMUM_MODULUS_BITS_QUANTITY)
+                .contains(&modulus_bits_quantity)
+            {
+                return Err(Box::from(ErrorKind::Logic));
+            }
+
+            let mut random_number_generator = thread_rng();
+
+            let (modulus, totient) = 'a: loop {
+                let first_prime =
+                    random_number_generator.gen_prime((modulus_bits_quantity / 2) as usize);
+
+                let second_prime = random_number_generator
+                    .gen_prime((modulus_bits_quantity - modulus_bits_quantity / 2) as usize);
+
+                let modulus_ = &first_prime * &second_prime;
+
+                if first_prime == second_prime
+                    || modulus_.bits() != (modulus_bits_quantity as usize)
+                {
+                    continue 'a;
+                }
+
+                let one = BigUint::from(1_u32);
+
+                break 'a (modulus_, (first_prime - &one) * (second_prime - &one));
+            };
+
+            let parameters = Self {
+                modulus: modulus.to_bytes_be(),
+                squarings_quantity,
+                trapdoor: Some(Trapdoor {
+                    totient: totient.to_bytes_be(),
+                }),
+            };
+
+            parameters.validate()?;
+
+            return Ok(parameters);
+        }
+
+        pub fn modulus_bits_quantity<'a>(&'a self) -> u32 {
+            return match self.modulus.iter().position(|byte| *byte != 0) {
+                Some(position) => {
+                    (((self.modulus.len() - position) * 8) as u32)
+                        - self.modulus[position].leading_zeros()
+                }
+                None => 0,
+            };
+        }
+
+        pub fn validate<'a>(&'a self) -> Result<(), Box<dyn Error + 'static>> {
+            if self.squarings_quantity == 0
+                || self.squarings_quantity > Self::MAXIMUM_SQUARINGS_QUANTITY
+                || !(Self::MINIMUM_MODULUS_BITS_QUANTITY..=Self::MAXIMUM_MODULUS_BITS_QUANTITY)
+                    .contains(&self.modulus_bits_quantity())
+            {
+                return Err(Box::from(ErrorKind::Logic));
+            }
+
+            return Ok(());
+        }
+    }
+
+    // The Euler totient of the modulus.
+    #[derive(Clone, PartialEq, Eq)]
+    pub struct Trapdoor {
+        totient: Vec<u8>,
+    }
+
+    // Is not printed to the logs.
+    impl Debug for Trapdoor {
+        fn fmt<'a>(&'a self, formatter: &'a mut Formatter<'_>) -> FmtResult {
+            write!(formatter, "Trapdoor({} bytes)", self.totient.len())
+        }
+    }
+
+    // The solution of the challenge. The single nonce for the hash based algorithms,
+    // the nonce with the indices of the colliding leaves for the Equihash,
+    // the big-endian X^(2^T) mod N for the time-lock puzzle.
+    // The nonces are of the variable length, the own solver uses the 8 big-endian bytes.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum Proof {
+        Nonce(Vec<u8>),
+        Solution { nonce: Vec<u8>, indices: Vec<u32> },
+        Residue(Vec<u8>),
+        // The distinct solutions of the multi-puzzle challenge.
+        Multiple(Vec<Proof>),
+    }
+
+    impl Proof {
+        pub const MAXIMUM_NONCE_LENGTH: usize = 32;
+    }
+
+    // The proof on the wire. The proof is encoded separately, so, its length is checked against the limit
+    // of the algorithm before the decoding, and the encoding may be changed with the version.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct ProofPayload {
+        pub version: u8,
+        pub algorithm: Algorithm,
+        pub data: Vec<u8>,
+    }
+
+    impl ProofPayload {
+        pub const VERSION: u8 = 1;
+
+        pub fn encode<'a>(
+            algorithm: Algorithm,
+            proof: &'a Proof,
+        ) -> Result<Self, Box<dyn Error + 'static>> {
+            return Ok(Self {
+                version: Self::VERSION,
+                algorithm,
+                data: Serializer::serialize(proof)?,
+            });
+        }
+
+        // Is None for the payload of the other version or algorithm, or of the exceeding length.
+        pub fn decode<'a>(&'a self, challenge: &'a Challenge) -> Option<Proof> {
+            if self.version != Self::VERSION
+                || self.algorithm != challenge.algorithm
+                || self.data.len()
+                    > challenge.algorithm.maximum_proof_length()
+                        * (challenge.puzzles_quantity as usize)
+            {
+                return None;
+            }
+
+            return Serializer::deserialize::<'_, Proof>(self.data.as_slice()).ok();
+        }
+    }
+
+    // The result of the work for the single nonce.
+    pub struct Attempt {
+        // The quantity of the candidates, that were checked against the difficulty.
+        pub attempts_quantity: u64,
+        pub proof: Option<Proof>,
+    }
+
+    impl Attempt {
+        // The proof is created for the satisfying nonce only.
+        pub fn nonce(is_satisfied: bool, nonce: u64) -> Self {
+            return Self {
+                attempts_quantity: 1,
+                proof: if is_satisfied {
+                    Some(Proof::Nonce(nonce.to_be_bytes().to_vec()))
+                } else {
+                    None
+                },
+            };
+        }
+    }
+
+    pub struct Cost {
+        pub algorithm: Algorithm,
+        pub difficulty: Difficulty,
+        pub hashes_per_attempt_quantity: u64,
+        pub memory_per_attempt_bytes_quantity: u64,
+        pub expected_attempts_quantity: f64,
+        pub expected_hashes_quantity: f64,
+    }
+
+    // The required quantity of leading zero bits of the resulting hash.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+    #[serde(try_from = "u8", into = "u8")]
+    pub struct Difficulty {
+        zero_bits: u8,
+    }
+
+    impl Difficulty {
+        pub const MINIMUM: Self = Self { zero_bits: 1 };
+        pub const MAXIMUM: Self = Self { zero_bits: 64 };
+        // The solve time depends on the hash rate of the machine, use the `Calibration` to measure it.
+        pub const I: Self = Self { zero_bits: 8 };
+        pub const II: Self = Self { zero_bits: 16 };
+        pub const III: Self = Self { zero_bits: 24 };
+        pub const IV: Self = Self { zero_bits: 32 };
+
+        pub fn new(zero_bits: u8) -> Result<Self, Box<dyn Error + 'static>> {
+            let difficulty = match Self::try_from(zero_bits) {
+                Ok(difficulty_) => difficulty_,
+                Err(error) => {
+                    return Err(Box::from(error));
+                }
+            };
+
+            return Ok(difficulty);
+        }
+
+        pub const fn zero_bits<'a>(&'a self) -> u8 {
+            return self.zero_bits;
+        }
+
+        pub fn raise<'a>(&'a self, zero_bits: u8) -> Self {
+            return Self {
+                zero_bits: self
+                    .zero_bits
+                    .saturating_add(zero_bits)
+                    .min(Self::MAXIMUM.zero_bits),
+            };
+        }
+
+        // Every attempt succeeds independently with the 2^(-zero bits) probability,
+        // so the quantity of attempts is geometrically distributed.
+        pub fn success_probability<'a>(&'a self) -> f64 {
+            return 2.0_f64.powi(-(self.zero_bits as i32));
+        }
+
+        pub fn expected_attempts_quantity<'a>(&'a self) -> f64 {
+            return 2.0_f64.powi(self.zero_bits as i32);
+        }
+
+        // The quantity of attempts, that is enough to find the nonce with the given probability from [0, 1).
+        // The probability of 1 is never reached, so, it is clamped below 1, and the quantile stays finite.
+        pub fn attempts_quantile<'a>(&'a self, probability: f64) -> f64 {
+            let probability_ = probability.clamp(0.0, 1.0 - f64::EPSILON);
+
+            return ((-probability_).ln_1p() / (-self.success_probability()).ln_1p()).ceil();
+        }
+
+        pub fn expected_time<'a>(&'a self, hash_rate: f64) -> Duration {
+            return Self::time(self.expected_attempts_quantity(), hash_rate);
+        }
+
+        pub fn quantile_time<'a>(&'a self, probability: f64, hash_rate: f64) -> Duration {
+            return Self::time(self.attempts_quantile(probability), hash_rate);
+        }
+
+        fn time(attempts_quantity: f64, hash_rate: f64) -> Duration {
+            return match Duration::try_from_secs_f64(attempts_quantity / hash_rate) {
+                Ok(duration) => duration,
+                Err(_) => Duration::MAX,
+            };
+        }
+
+        // The difficulty of the every of the given quantity of puzzles, so, that the expected work is kept.
+        // The quantity of the attempts is the sum of the geometrically distributed quantities, so, the relative
+        // standard deviation of the solve time decreases as 1/sqrt(puzzles quantity).
+        pub fn split<'a>(&'a self, puzzles_quantity: u16) -> Self {
+            return self.lower(puzzles_quantity.max(1).ilog2() as u8);
+        }
+
+        // Lowers the difficulty by the given quantity of bits, saturating at `Difficulty::MINIMUM`.
+        pub fn lower<'a>(&'a self, zero_bits: u8) -> Self {
+            return Self {
+                zero_bits: self
+                    .zero_bits
+                    .saturating_sub(zero_bits)
+                    .max(Self::MINIMUM.zero_bits),
+            };
+        }
+
+        // The hash has at least the required quantity of leading zero bits.
+        pub fn is_satisfied_by<'a>(&'a self, hash: &'a [u8]) -> bool {
+            let zero_bytes_quantity = (self.zero_bits / 8) as usize;
+
+            let zero_bits_remainder = (self.zero_bits % 8) as u32;
+
+            if hash.len() <= zero_bytes_quantity {
+                return false;
+            }
+
+            for byte in hash[..zero_bytes_quantity].iter() {
+                if *byte != 0 {
+                    return false;
+                }
+            }
+
+            return hash[zero_bytes_quantity].leading_zeros() >= zero_bits_remainder;
+        }
+    }
+
+    impl TryFrom<u8> for Difficulty {
+        type Error = ErrorKind;
+
+        fn try_from(zero_bits: u8) -> Result<Self, Self::Error> {
+            if !(Self::MINIMUM.zero_bits..=Self::MAXIMUM.zero_bits).contains(&zero_bits) {
+                return Err(ErrorKind::Logic);
+            }
+
+            return Ok(Self { zero_bits });
+        }
+    }
+
+    impl From<Difficulty> for u8 {
+        fn from(difficulty: Difficulty) -> Self {
+            return difficulty.zero_bits;
+        }
+    }
+
+    // Measures the hash rate (attempts per second) of the algorithm on the current machine
+    // and maps the difficulties onto the solve time.
+    pub struct Calibration {
+        pub algorithm: Algorithm,
+        pub attempts_quantity: u64,
+        pub elapsed: Duration,
+        pub hash_rate: f64,
+    }
+
+    impl Calibration {
+        // The batch grows while it takes less time, so the clock is not read after the every cheap attempt.
+        const BATCH_DURATION: Duration = Duration::from_millis(10);
+
+        // The solve time of the time-lock puzzle does not depend on the difficulty, so, it is not measured.
+        pub fn measure(
+            algorithm: Algorithm,
+            duration: Duration,
+        ) -> Result<Self, Box<dyn Error + 'static>> {
+            if algorithm == Algorithm::TimeLock {
+                return Err(Box::from(
+                    "The time-lock puzzle is not calibrated, its solve time is set by the quantity of the squarings, not by the difficulty.",
+                ));
+            }
+
+            let mut p_o_w = POW::new(algorithm)?;
+
+            let challenge = p_o_w.generate_challenge(Difficulty::MAXIMUM);
+
+            let mut nonce: u64 = thread_rng().gen_range(0..u64::MAX);
+
+            let mut attempts_quantity: u64 = 0;
+
+            let mut batch_size: u64 = 1;
+
+            let start = Instant::now();
+
+            let elapsed = 'a: loop {
+                let batch_start = Instant::now();
+
+                for _ in 0..batch_size {
+                    attempts_quantity += p_o_w.attempt(&challenge, nonce)?.attempts_quantity;
+
+                    nonce = nonce.wrapping_add(1);
+                }
+
+                if batch_start.elapsed() < Self::BATCH_DURATION {
+                    batch_size = batch_size.saturating_mul(2);
+                }
+
+                let elapsed_ = start.elapsed();
+
+                if elapsed_ >= duration {
+                    break 'a elapsed_;
+                }
+            };
+
+            return Ok(Self {
+                algorithm,
+                attempts_quantity,
+                elapsed,
+                hash_rate: (attempts_quantity as f64) / elapsed.as_secs_f64(),
+            });
+        }
+
+        pub fn estimate<'a>(&'a self, difficulty: Difficulty) -> Estimate {
+            return Estimate {
+                difficulty,
+                expected_attempts_quantity: difficulty.expected_attempts_quantity(),
+                mean_time: difficulty.expected_time(self.hash_rate),
+                percentile_95_time: difficulty.quantile_time(0.95, self.hash_rate),
+                percentile_99_time: difficulty.quantile_time(0.99, self.hash_rate),
+            };
+        }
+
+        // The hardest difficulty, which mean solve time does not exceed the target. Is None, if even the minimum
+        // difficulty exceeds it.
+        pub fn recommend<'a>(&'a self, target: Duration) -> Option<Estimate> {
+            let mut estimate: Option<Estimate> = None;
+
+            'a: for zero_bits in Difficulty::MINIMUM.zero_bits()..=Difficulty::MAXIMUM.zero_bits() {
+                let estimate_ = self.estimate(Difficulty { zero_bits });
+
+                if estimate_.mean_time > target {
+                    break 'a;
+                }
+
+                estimate = Some(estimate_);
+            }
+
+            return estimate;
+        }
+    }
+
+    pub struct Estimate {
+        pub difficulty: Difficulty,
+        pub expected_attempts_quantity: f64,
+        pub mean_time: Duration,
+        pub percentile_95_time: Duration,
+        pub percentile_99_time: Duration,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+    pub struct Secret {
+        pub value: Uuid,
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::Algorithm;
+        use super::Calibration;
+        use super::Difficulty;
+        use std::time::Duration;
+
+        fn calibration(hash_rate: f64) -> Calibration {
+            return Calibration {
+                algorithm: Algorithm::DoubleSha256,
+                attempts_quantity: 0,
+                elapsed: Duration::ZERO,
+                hash_rate,
+            };
+        }
+
+        #[test]
+        fn success_probability_and_expected_attempts_are_powers_of_two() -> () {
+            for zero_bits in Difficulty::MINIMUM.zero_bits()..=Difficulty::MAXIMUM.zero_bits() {
+                let difficulty = Difficulty::new(zero_bits).unwrap();
+
+                assert_eq!(
+                    difficulty.success_probability(),
+                    1.0 / 2.0_f64.powi(zero_bits as i32)
+                );
+
+                assert_eq!(
+                    difficulty.expected_attempts_quantity(),
+                    2.0_f64.powi(zero_bits as i32)
+                );
+
+                assert_eq!(
+                    difficulty.success_probability() * difficulty.expected_attempts_quantity(),
+                    1.0
+                );
+            }
+
+            assert_eq!(Difficulty::MINIMUM.success_probability(), 0.5);
+
+            assert_eq!(Difficulty::I.expected_attempts_quantity(), 256.0);
+
+            return ();
+        }
+
+        #[test]
+        fn attempts_quantile_follows_closed_form() -> () {
+            assert_eq!(Difficulty::MINIMUM.attempts_quantile(0.5), 1.0);
+
+            assert_eq!(Difficulty::MINIMUM.attempts_quantile(0.75), 2.0);
+
+            for zero_bits in [4, 8, 16, 24, 32] {
+                let difficulty = Difficulty::new(zero_bits).unwrap();
+
+                let expected_attempts_quantity = difficulty.expected_attempts_quantity();
+
+                for probability in [0.5_f64, 0.95, 0.99] {
+                    // ln(1 - q) / ln(1 - p), which is about -ln(1 - q) / p for the small p.
+                    let quantile = ((1.0 - probability).ln()
+                        / (1.0 - difficulty.success_probability()).ln())
+                    .ceil();
+
+                    assert!((difficulty.attempts_quantile(probability) - quantile).abs() <= 1.0);
+
+                    if zero_bits >= 16 {
+                        let approximation = -(1.0 - probability).ln() * expected_attempts_quantity;
+
+                        assert!(
+                            (difficulty.attempts_quantile(probability) - approximation).abs()
+                                / approximation
+                                < 1e-3
+                        );
+                    }
+                }
+            }
+
+            return ();
+        }
+
+        #[test]
+        fn attempts_quantile_is_monotonic() -> () {
+            for zero_bits in Difficulty::MINIMUM.zero_bits()..=Difficulty::MAXIMUM.zero_bits() {
+                let difficulty = Difficulty::new(zero_bits).unwrap();
+
+                let mut last_quantile = 0.0;
+
+                for percent in 0..100 {
+                    let quantile = difficulty.attempts_quantile((percent as f64) / 100.0);
+
+                    assert!(quantile >= last_quantile);
+
+                    last_quantile = quantile;
+                }
+
+                if zero_bits < Difficulty::MAXIMUM.zero_bits() {
+                    assert!(
+                        Difficulty::new(zero_bits + 1)
+                            .unwrap()
+                            .attempts_quantile(0.95)
+                            > difficulty.attempts_quantile(0.95)
+                    );
+                }
+            }
+
+            return ();
+        }
+
+        #[test]
+        fn expected_time_is_attempts_divided_by_hash_rate() -> () {
+            assert_eq!(Difficulty::I.expected_time(256.0), Duration::from_secs(1));
+
+            assert_eq!(
+                Difficulty::II.expected_time(1024.0),
+                Duration::from_secs(64)
+            );
+
+            assert_eq!(
+                Difficulty::IV.expected_time(1_000_000.0),
+                Duration::from_secs_f64(4294.967296)
+            );
+
+            assert_eq!(
+                Difficulty::I.expected_time(512_000.0),
+                Duration::from_micros(500)
+            );
+
+            return ();
+        }
+
+        #[test]
+        fn statistics_stay_finite_at_edges() -> () {
+            for difficulty in [Difficulty::MINIMUM, Difficulty::MAXIMUM] {
+                assert!(difficulty.success_probability() > 0.0);
+
+                assert!(difficulty.expected_attempts_quantity().is_finite());
+
+                for probability in [0.0, f64::MIN_POSITIVE, 1e-12, 0.5, 1.0 - 1e-12, 1.0] {
+                    let quantile = difficulty.attempts_quantile(probability);
+
+                    assert!(quantile.is_finite());
+
+                    assert!(quantile >= 0.0);
+                }
+            }
+
+            assert_eq!(
+                Difficulty::MAXIMUM.expected_attempts_quantity(),
+                2.0_f64.powi(64)
+            );
+
+            assert_eq!(
+                Difficulty::MAXIMUM.success_probability(),
+                1.0 / 2.0_f64.powi(64)
+            );
+
+            assert!(Difficulty::MAXIMUM.attempts_quantile(0.5) > 1e19);
+
+            assert_eq!(Difficulty::MAXIMUM.expected_time(0.0), Duration::MAX);
+
+            assert_eq!(Difficulty::MAXIMUM.quantile_time(1.0, 1.0), Duration::MAX);
+
+            return ();
+        }
+
+        #[test]
+        fn estimate_follows_geometric_distribution() -> () {
+            let estimate = calibration(1024.0).estimate(Difficulty::new(10).unwrap());
+
+            assert_eq!(estimate.expected_attempts_quantity, 1024.0);
+
+            assert_eq!(estimate.mean_time, Duration::from_secs(1));
+
+            // The quantile of the geometric distribution: ln(1 - q) / ln(1 - p).
+            let percentile_95_attempts_quantity =
+                ((0.05_f64).ln() / (1.0 - 1.0 / 1024.0_f64).ln()).ceil();
+
+            assert_eq!(
+                estimate.percentile_95_time,
+                Duration::from_secs_f64(percentile_95_attempts_quantity / 1024.0)
+            );
+
+            assert!(estimate.mean_time < estimate.percentile_95_time);
+
+            assert!(estimate.percentile_95_time < estimate.percentile_99_time);
+
+            return ();
+        }
+
+        #[test]
+        fn hardest_difficulty_within_target_is_recommended() -> () {
+            let calibration_ = calibration(1024.0);
+
+            assert_eq!(
+                calibration_
+                    .recommend(Duration::from_secs(1))
+                    .unwrap()
+                    .difficulty
+                    .zero_bits(),
+                10
+            );
+
+            assert_eq!(
+                calibration_
+                    .recommend(Duration::from_millis(1999))
+                    .unwrap()
+                    .difficulty
+                    .zero_bits(),
+                10
+            );
+
+            assert_eq!(
+                calibration_
+                    .recommend(Duration::from_millis(999))
+                    .unwrap()
+                    .difficulty
+                    .zero_bits(),
+                9
+            );
+
+            assert_eq!(
+                calibration(f64::MAX)
+                    .recommend(Duration::MAX)
+                    .unwrap()
+                    .difficulty,
+                Difficulty::MAXIMUM
+            );
+
+            return ();
+        }
+
+        #[test]
+        fn no_difficulty_is_recommended_for_unreachable_target() -> () {
+            // The minimum difficulty takes 2 attempts, that are about 1.95 ms.
+            assert!(calibration(1024.0)
+                .recommend(Duration::from_millis(1))
+                .is_none());
+
+            assert!(calibration(1024.0)
+                .recommend(Duration::from_millis(2))
+                .is_some());
+
+            return ();
+        }
+
+        #[test]
+        fn hash_rate_is_measured() -> () {
+            let calibration_ =
+                Calibration::measure(Algorithm::DoubleSha256, Duration::from_millis(50)).unwrap();
+
+            assert!(calibration_.attempts_quantity > 0);
+
+            assert!(calibration_.elapsed >= Duration::from_millis(50));
+
+            assert!(calibration_.hash_rate > 0.0);
+
+            return ();
+        }
+
+        #[test]
+        fn time_lock_is_not_measured() -> () {
+            assert!(Calibration::measure(Algorithm::TimeLock, Duration::from_millis(50)).is_err());
+
+            return ();
+        }
+
+        #[cfg(any(feature = "double_sha256", feature = "sha256"))]
+        mod sha256 {
+            use super::super::Algorithm;
+            use super::super::Difficulty;
+            use super::super::Sha256ProofOfWork;
+            use super::super::POW;
+            use crypto::digest::Digest as _;
+            use crypto::sha2::Sha256 as PortableSha256;
+            use rand::thread_rng;
+            use rand::Rng;
+            use sha2::Digest as _;
+            use sha2::Sha256;
+
+            // The secret lengths around the boundaries of the padding (55 and 56 bytes) and of the block (64 bytes).
+            const SECRET_LENGTHS: [usize; 18] = [
+                0, 1, 31, 47, 48, 54, 55, 56, 57, 63, 64, 65, 118, 119, 120, 127, 128, 129,
+            ];
+
+            const NONCE_LENGTHS: [usize; 6] = [0, 1, 7, 8, 9, 32];
+
+            fn hash<'a>(rounds_quantity: u32, secret: &'a [u8], nonce: &'a [u8]) -> [u8; 32] {
+                let mut sha256_proof_of_work =
+                    Sha256ProofOfWork::new(Algorithm::DoubleSha256, rounds_quantity);
+
+                sha256_proof_of_work.buffer = secret.to_vec();
+
+                sha256_proof_of_work.prepare_blocks(nonce.len());
+
+                sha256_proof_of_work.check_nonce(Difficulty::MINIMUM, nonce);
+
+                return sha256_proof_of_work.result_hash;
+            }
+
+            fn random_bytes(length: usize) -> Vec<u8> {
+                let mut rng = thread_rng();
+
+                return (0..length).map(|_| rng.gen::<u8>()).collect();
+            }
+
+            #[test]
+            fn midstate_hash_equals_reference_digest() -> () {
+                for secret_length in SECRET_LENGTHS {
+                    for nonce_length in NONCE_LENGTHS {
+                        let secret = random_bytes(secret_length);
+
+                        let nonce = random_bytes(nonce_length);
+
+                        let mut message = secret.clone();
+
+                        message.extend_from_slice(nonce.as_slice());
+
+                        let single_hash = Sha256::digest(message.as_slice());
+
+                        let double_hash = Sha256::digest(single_hash.as_slice());
+
+                        assert_eq!(
+                            hash(1, secret.as_slice(), nonce.as_slice()).as_slice(),
+                            single_hash.as_slice()
+                        );
+
+                        assert_eq!(
+                            hash(2, secret.as_slice(), nonce.as_slice()).as_slice(),
+                            double_hash.as_slice()
+                        );
+                    }
+                }
+
+                return ();
+            }
+
+            #[test]
+            fn prepared_blocks_are_reused_for_other_nonce() -> () {
+                for secret_length in SECRET_LENGTHS {
+                    let secret = random_bytes(secret_length);
+
+                    let mut sha256_proof_of_work =
+                        Sha256ProofOfWork::new(Algorithm::DoubleSha256, 2);
+
+                    sha256_proof_of_work.buffer = secret.clone();
+
+                    sha256_proof_of_work.prepare_blocks(8);
+
+                    for nonce in [0_u64, 1, u64::MAX] {
+                        sha256_proof_of_work
+                            .check_nonce(Difficulty::MINIMUM, nonce.to_be_bytes().as_slice());
+
+                        let mut message = secret.clone();
+
+                        message.extend_from_slice(nonce.to_be_bytes().as_slice());
+
+                        assert_eq!(
+                            sha256_proof_of_work.result_hash.as_slice(),
+                            Sha256::digest(Sha256::digest(message.as_slice()).as_slice())
+                                .as_slice()
+                        );
+                    }
+                }
+
+                return ();
+            }
+
+            // The compression of the sha2 crate uses the SHA extensions of the processor, if they are detected,
+            // and the rust-crypto one is portable only, so, both paths are compared, where the extensions exist.
+            #[test]
+            fn detected_backend_agrees_with_portable_implementation() -> () {
+                let mut portable_sha256 = PortableSha256::new();
+
+                let mut portable_hash = [0_u8; 32];
+
+                for secret_length in SECRET_LENGTHS {
+                    let secret = random_bytes(secret_length);
+
+                    let nonce = random_bytes(8);
+
+                    let mut message = secret.clone();
+
+                    message.extend_from_slice(nonce.as_slice());
+
+                    portable_sha256.reset();
+
+                    portable_sha256.input(message.as_slice());
+
+                    portable_sha256.result(&mut portable_hash);
+
+                    assert_eq!(
+                        Sha256::digest(message.as_slice()).as_slice(),
+                        portable_hash.as_slice(),
+                        "The {} backend differs.",
+                        POW::sha256_backend()
+                    );
+
+                    assert_eq!(
+                        hash(1, secret.as_slice(), nonce.as_slice()).as_slice(),
+                        portable_hash.as_slice()
+                    );
+                }
+
+                return ();
+            }
+        }
+
+        #[cfg(feature = "double_sha256")]
+        mod multi_puzzle {
+            use super::super::Algorithm;
+            use super::super::Challenge;
+            use super::super::Difficulty;
+            use super::super::Proof;
+            use super::super::POW;
+
+            const PUZZLES_QUANTITY: u16 = 4;
+
+            fn solve() -> (POW, Challenge, Vec<Proof>) {
+                let mut p_o_w = POW::new(Algorithm::DoubleSha256).unwrap();
+
+                let challenge = p_o_w
+                    .generate_multi_puzzle_challenge(Difficulty::I, PUZZLES_QUANTITY)
+                    .unwrap();
+
+                return match p_o_w.find_nonce(&challenge).unwrap() {
+                    Proof::Multiple(proofs) => (p_o_w, challenge, proofs),
+                    _ => panic!("The multiple solutions are not found."),
+                };
+            }
+
+            // The nonce, that does not solve the puzzle.
+            fn wrong_proof<'a>(p_o_w: &'a mut POW, challenge: &'a Challenge) -> Proof {
+                let mut single_challenge = challenge.clone();
+
+                single_challenge.puzzles_quantity = 1;
+
+                let mut nonce: u64 = 0;
+
+                'a: loop {
+                    let proof = Proof::Nonce(nonce.to_be_bytes().to_vec());
+
+                    if !p_o_w.verify_nonce(&single_challenge, &proof).unwrap() {
+                        break 'a proof;
+                    }
+
+                    nonce += 1;
+                }
+            }
+
+            #[test]
+            fn distinct_solutions_are_verified() -> () {
+                let (mut p_o_w, challenge, mut proofs) = solve();
+
+                assert_eq!(proofs.len(), PUZZLES_QUANTITY as usize);
+
+                for (index, proof) in proofs.iter().enumerate() {
+                    assert!(!proofs[..index].contains(proof));
+                }
+
+                assert!(p_o_w
+                    .verify_nonce(&challenge, &Proof::Multiple(proofs.clone()))
+                    .unwrap());
+
+                // The order of the solutions does not matter.
+                proofs.reverse();
+
+                assert!(p_o_w
+                    .verify_nonce(&challenge, &Proof::Multiple(proofs))
+                    .unwrap());
+
+                let proof_payload = p_o_w.solve(&challenge).unwrap();
+
+                assert!(p_o_w.verify(&challenge, &proof_payload).unwrap());
+
+                return ();
+            }
+
+            #[test]
+            fn duplicate_solutions_are_rejected() -> () {
+                let (mut p_o_w, challenge, mut proofs) = solve();
+
+                proofs[PUZZLES_QUANTITY as usize - 1] = proofs[0].clone();
+
+                assert!(!p_o_w
+                    .verify_nonce(&challenge, &Proof::Multiple(proofs.clone()))
+                    .unwrap());
+
+                let same_proofs = vec![proofs[0].clone(); PUZZLES_QUANTITY as usize];
+
+                assert!(!p_o_w
+                    .verify_nonce(&challenge, &Proof::Multiple(same_proofs))
+                    .unwrap());
+
+                return ();
+            }
+
+            #[test]
+            fn wrong_quantity_of_solutions_is_rejected() -> () {
+                let (mut p_o_w, challenge, proofs) = solve();
+
+                let too_few_proofs = proofs[..(PUZZLES_QUANTITY as usize - 1)].to_vec();
+
+                assert!(!p_o_w
+                    .verify_nonce(&challenge, &Proof::Multiple(too_few_proofs))
+                    .unwrap());
+
+                assert!(!p_o_w
+                    .verify_nonce(&challenge, &Proof::Multiple(vec![]))
+                    .unwrap());
+
+                // The extra solution is the valid one, but is not expected.
+                let mut larger_challenge = challenge.clone();
+
+                larger_challenge.puzzles_quantity = PUZZLES_QUANTITY + 1;
+
+                let larger_proofs = match p_o_w.find_nonce(&larger_challenge).unwrap() {
+                    Proof::Multiple(larger_proofs_) => larger_proofs_,
+                    _ => panic!("The multiple solutions are not found."),
+                };
+
+                assert!(!p_o_w
+                    .verify_nonce(&challenge, &Proof::Multiple(larger_proofs))
+                    .unwrap());
+
+                // The single solution is not the multiple one.
+                assert!(!p_o_w.verify_nonce(&challenge, &proofs[0]).unwrap());
+
+                return ();
+            }
+
+            #[test]
+            fn wrong_sub_solution_is_rejected() -> () {
+                let (mut p_o_w, challenge, mut proofs) = solve();
+
+                proofs[1] = wrong_proof(&mut p_o_w, &challenge);
+
+                assert!(!p_o_w
+                    .verify_nonce(&challenge, &Proof::Multiple(proofs))
+                    .unwrap());
+
+                return ();
+            }
+
+            #[test]
+            fn puzzles_quantity_is_bounded() -> () {
+                let p_o_w = POW::new(Algorithm::DoubleSha256).unwrap();
+
+                assert!(p_o_w
+                    .generate_multi_puzzle_challenge(Difficulty::I, 0)
+                    .is_err());
+
+                assert!(p_o_w
+                    .generate_multi_puzzle_challenge(
+                        Difficulty::I,
+                        Challenge::MAXIMUM_PUZZLES_QUANTITY + 1
+                    )
+                    .is_err());
+
+                assert!(p_o_w
+                    .generate_multi_puzzle_challenge(
+                        Difficulty::I,
+                        Challenge::MAXIMUM_PUZZLES_QUANTITY
+                    )
+                    .is_ok());
+
+                return ();
+            }
+        }
+
+        #[cfg(feature = "double_sha256")]
+        mod solver {
+            use super::super::Algorithm;
+            use super::super::CancellationToken;
+            use super::super::Challenge;
+            use super::super::Difficulty;
+            use super::super::Interruption;
+            use super::super::Progress;
+            use super::super::Solver;
+            use super::super::POW;
+            use std::error::Error;
+            use std::time::Duration;
+            use std::time::Instant;
+            use tokio::spawn;
+            use tokio::sync::mpsc::channel;
+            use tokio::time::sleep;
+            use tokio::time::timeout;
+
+            // Is not solved within the test.
+            fn unsolvable_challenge() -> (POW, Challenge) {
+                let p_o_w = POW::new(Algorithm::DoubleSha256).unwrap();
+
+                let challenge = p_o_w.generate_challenge(Difficulty::MAXIMUM);
+
+                return (p_o_w, challenge);
+            }
+
+            fn interruption<'a>(error: &'a (dyn Error + 'static)) -> Interruption {
+                return *error.downcast_ref::<Interruption>().unwrap();
+            }
+
+            #[tokio::test]
+            async fn challenge_is_solved() -> () {
+                let mut p_o_w = POW::new(Algorithm::DoubleSha256).unwrap();
+
+                let challenge = p_o_w
+                    .generate_multi_puzzle_challenge(Difficulty::I, 4)
+                    .unwrap();
+
+                let proof_payload = Solver::new(CancellationToken::new())
+                    .solve(
+                        POW::new(Algorithm::DoubleSha256).unwrap(),
+                        challenge.clone(),
+                    )
+                    .await
+                    .unwrap();
+
+                assert!(p_o_w.verify(&challenge, &proof_payload).unwrap());
+
+                return ();
+            }
+
+            #[tokio::test]
+            async fn cancellation_token_stops_search() -> () {
+                let (p_o_w, challenge) = unsolvable_challenge();
+
+                let cancellation_token = CancellationToken::new();
+
+                let cancellation_token_ = cancellation_token.clone();
+
+                spawn(async move {
+                    sleep(Duration::from_millis(50)).await;
+
+                    cancellation_token_.cancel();
+                });
+
+                let result = timeout(
+                    Duration::from_secs(5),
+                    Solver::new(cancellation_token.clone()).solve(p_o_w, challenge.clone()),
+                )
+                .await
+                .unwrap();
+
+                assert_eq!(
+                    interruption(result.unwrap_err().as_ref()),
+                    Interruption::Cancelled
+                );
+
+                // The cancelled token stops the next search before the first attempt.
+                let result_ = Solver::new(cancellation_token)
+                    .solve(POW::new(Algorithm::DoubleSha256).unwrap(), challenge)
+                    .await;
+
+                assert_eq!(
+                    interruption(result_.unwrap_err().as_ref()),
+                    Interruption::Cancelled
+                );
+
+                return ();
+            }
+
+            #[tokio::test]
+            async fn deadline_stops_search() -> () {
+                let (p_o_w, challenge) = unsolvable_challenge();
+
+                let mut solver = Solver::new(CancellationToken::new());
+
+                let start = Instant::now();
+
+                solver.deadline = Some(start + Duration::from_millis(50));
+
+                let result = timeout(Duration::from_secs(5), solver.solve(p_o_w, challenge))
+                    .await
+                    .unwrap();
+
+                assert_eq!(
+                    interruption(result.unwrap_err().as_ref()),
+                    Interruption::DeadlineExceeded
+                );
+
+                assert!(start.elapsed() >= Duration::from_millis(50));
+
+                return ();
+            }
+
+            #[tokio::test]
+            async fn progress_is_reported() -> () {
+                let (p_o_w, challenge) = unsolvable_challenge();
+
+                let (sender, mut receiver) = channel::<Progress>(64);
+
+                let mut solver = Solver::new(CancellationToken::new());
+
+                solver.deadline = Some(Instant::now() + Duration::from_millis(300));
+
+                solver.progress_sender = Some(sender);
+
+                solver.progress_interval = Duration::from_millis(20);
+
+                let result = solver.solve(p_o_w, challenge).await;
+
+                assert!(result.is_err());
+
+                // The senders are dropped, so, the receiver gets all of the reported progress.
+                drop(solver);
+
+                let mut progresses: Vec<Progress> = vec![];
+
+                while let Some(progress) = receiver.recv().await {
+                    progresses.push(progress);
+                }
+
+                assert!(progresses.len() >= 2);
+
+                for (index, progress) in progresses.iter().enumerate() {
+                    assert_eq!(progress.solved_puzzles_quantity, 0);
+
+                    assert!(progress.hash_rate > 0.0);
+
+                    assert!(progress.estimated_remaining_time.is_some());
+
+                    if index > 0 {
+                        assert!(
+                            progress.attempts_quantity > progresses[index - 1].attempts_quantity
+                        );
+
+                        assert!(progress.elapsed > progresses[index - 1].elapsed);
+                    }
+                }
+
+                return ();
+            }
+
+            #[tokio::test]
+            async fn dropped_future_stops_search() -> () {
+                let (p_o_w, challenge) = unsolvable_challenge();
+
+                let (sender, mut receiver) = channel::<Progress>(1);
+
+                let mut solver = Solver::new(CancellationToken::new());
+
+                solver.progress_sender = Some(sender);
+
+                solver.progress_interval = Duration::from_millis(10);
+
+                assert!(
+                    timeout(Duration::from_millis(50), solver.solve(p_o_w, challenge))
+                        .await
+                        .is_err()
+                );
+
+                drop(solver);
+
+                // The search drops its sender, when it is stopped.
+                timeout(Duration::from_secs(5), async move {
+                    while receiver.recv().await.is_some() {}
+                })
+                .await
+                .unwrap();
+
+                return ();
+            }
+        }
+
+        #[cfg(feature = "time_lock")]
+        mod time_lock {
+            use super::super::Algorithm;
+            use super::super::Challenge;
+            use super::super::Difficulty;
+            use super::super::Parameters;
+            use super::super::Proof;
+            use super::super::SequentialSquaringParameters;
+            use super::super::POW;
+            use crate::Serializer;
+            use crypto::digest::Digest;
+            use crypto::sha2::Sha256;
+            use num_bigint::BigUint;
+
+            const SQUARINGS_QUANTITY: u64 = 1000;
+
+            // The smallest modulus, so, the key is generated quickly.
+            fn generate() -> (POW, Challenge) {
+                let parameters = SequentialSquaringParameters::generate(
+                    SequentialSquaringParameters::MINIMUM_MODULUS_BITS_QUANTITY,
+                    SQUARINGS_QUANTITY,
+                )
+                .unwrap();
+
+                let p_o_w = POW::new_with_parameters(
+                    Algorithm::TimeLock,
+                    Parameters::SequentialSquaring(parameters),
+                )
+                .unwrap();
+
+                let challenge = p_o_w.generate_challenge(Difficulty::MINIMUM);
+
+                return (p_o_w, challenge);
+            }
+
+            fn with_squarings_quantity<'a>(
+                challenge: &'a Challenge,
+                squarings_quantity: u64,
+            ) -> Challenge {
+                let mut challenge_ = challenge.clone();
+
+                if let Parameters::SequentialSquaring(ref mut parameters) = challenge_.parameters {
+                    parameters.squarings_quantity = squarings_quantity;
+                }
+
+                return challenge_;
+            }
+
+            fn without_trapdoor<'a>(challenge: &'a Challenge) -> Challenge {
+                let mut challenge_ = challenge.clone();
+
+                if let Parameters::SequentialSquaring(ref mut parameters) = challenge_.parameters {
+                    parameters.trapdoor = None;
+                }
+
+                return challenge_;
+            }
+
+            // X^(2^T) mod N, where X is the SHA-256 of the serialized secret modulo N.
+            fn residue<'a>(challenge: &'a Challenge, squarings_quantity: u64) -> Vec<u8> {
+                let modulus = match challenge.parameters {
+                    Parameters::SequentialSquaring(ref parameters) => {
+                        BigUint::from_bytes_be(parameters.modulus.as_slice())
+                    }
+                    _ => panic!("The challenge is not the time-lock one."),
+                };
+
+                let mut sha256 = Sha256::new();
+
+                sha256.input(Serializer::serialize(&challenge.secret).unwrap().as_slice());
+
+                let mut hash = [0_u8; 32];
+
+                sha256.result(&mut hash);
+
+                let base = BigUint::from_bytes_be(hash.as_slice()) % &modulus;
+
+                return base
+                    .modpow(
+                        &(BigUint::from(1_u32) << (squarings_quantity as usize)),
+                        &modulus,
+                    )
+                    .to_bytes_be();
+            }
+
+            #[test]
+            fn solution_is_sequential_squarings() -> () {
+                let (mut p_o_w, challenge) = generate();
+
+                let proof = p_o_w.find_nonce(&without_trapdoor(&challenge)).unwrap();
+
+                assert_eq!(
+                    proof,
+                    Proof::Residue(residue(&challenge, SQUARINGS_QUANTITY))
+                );
+
+                // The trapdoor shortcut and the squarings agree.
+                assert!(p_o_w.verify_nonce(&challenge, &proof).unwrap());
+
+                assert!(p_o_w
+                    .verify_nonce(&without_trapdoor(&challenge), &proof)
+                    .unwrap());
+
+                return ();
+            }
+
+            #[test]
+            fn wrong_solution_is_rejected() -> () {
+                let (mut p_o_w, challenge) = generate();
+
+                let mut residue_ = residue(&challenge, SQUARINGS_QUANTITY);
+
+                let last = residue_.len() - 1;
+
+                residue_[last] ^= 1;
+
+                assert!(!p_o_w
+                    .verify_nonce(&challenge, &Proof::Residue(residue_.clone()))
+                    .unwrap());
+
+                assert!(!p_o_w
+                    .verify_nonce(&without_trapdoor(&challenge), &Proof::Residue(residue_))
+                    .unwrap());
+
+                // The residue, that is not reduced by the modulus.
+                let modulus = match challenge.parameters {
+                    Parameters::SequentialSquaring(ref parameters) => {
+                        BigUint::from_bytes_be(parameters.modulus.as_slice())
+                    }
+                    _ => panic!("The challenge is not the time-lock one."),
+                };
+
+                let unreduced_residue =
+                    BigUint::from_bytes_be(residue(&challenge, SQUARINGS_QUANTITY).as_slice())
+                        + &modulus;
+
+                assert!(!p_o_w
+                    .verify_nonce(&challenge, &Proof::Residue(unreduced_residue.to_bytes_be()))
+                    .unwrap());
+
+                assert!(!p_o_w
+                    .verify_nonce(&challenge, &Proof::Nonce(vec![0; 8]))
+                    .unwrap());
+
+                return ();
+            }
+
+            #[test]
+            fn solution_of_other_squarings_quantity_is_rejected() -> () {
+                let (mut p_o_w, challenge) = generate();
+
+                for squarings_quantity in [SQUARINGS_QUANTITY - 1, SQUARINGS_QUANTITY + 1] {
+                    let proof = Proof::Residue(residue(&challenge, squarings_quantity));
+
+                    assert!(!p_o_w.verify_nonce(&challenge, &proof).unwrap());
+
+                    assert!(!p_o_w
+                        .verify_nonce(&without_trapdoor(&challenge), &proof)
+                        .unwrap());
+                }
+
+                // The solution of the changed T does not solve the issued challenge.
+                let proof = p_o_w
+                    .find_nonce(&with_squarings_quantity(
+                        &without_trapdoor(&challenge),
+                        SQUARINGS_QUANTITY + 1,
+                    ))
+                    .unwrap();
+
+                assert!(!p_o_w.verify_nonce(&challenge, &proof).unwrap());
+
+                assert!(p_o_w
+                    .verify_nonce(
+                        &with_squarings_quantity(&challenge, SQUARINGS_QUANTITY + 1),
+                        &proof
+                    )
+                    .unwrap());
+
+                return ();
+            }
+        }
+
+        #[cfg(feature = "equihash")]
+        mod equihash {
+            use super::super::Algorithm;
+            use super::super::Challenge;
+            use super::super::Difficulty;
+            use super::super::EquihashNode;
+            use super::super::GeneralizedBirthdayParameters;
+            use super::super::Parameters;
+            use super::super::Proof;
+            use super::super::POW;
+            use std::mem::size_of;
+
+            // 2^11 leaves and 8 indices.
+            const PARAMETERS: GeneralizedBirthdayParameters = GeneralizedBirthdayParameters {
+                bits_quantity: 40,
+                rounds_quantity: 3,
+            };
+
+            fn solve() -> (POW, Challenge, Vec<u8>, Vec<u32>) {
+                let mut p_o_w = POW::new_with_parameters(
+                    Algorithm::Equihash,
+                    Parameters::GeneralizedBirthday(PARAMETERS),
+                )
+                .unwrap();
+
+                let challenge = p_o_w.generate_challenge(Difficulty::MINIMUM);
+
+                return match p_o_w.find_nonce(&challenge).unwrap() {
+                    Proof::Solution { nonce, indices } => (p_o_w, challenge, nonce, indices),
+                    _ => panic!("The solution is not found."),
+                };
+            }
+
+            #[test]
+            fn solution_is_verified() -> () {
+                let (mut p_o_w, challenge, nonce, indices) = solve();
+
+                assert_eq!(indices.len(), PARAMETERS.solution_length());
+
+                let proof_payload = p_o_w.solve(&challenge).unwrap();
+
+                assert!(p_o_w.verify(&challenge, &proof_payload).unwrap());
+
+                assert!(p_o_w
+                    .verify_nonce(&challenge, &Proof::Solution { nonce, indices })
+                    .unwrap());
+
+                return ();
+            }
+
+            #[test]
+            fn duplicate_indices_are_rejected() -> () {
+                let (mut p_o_w, challenge, nonce, mut indices) = solve();
+
+                indices[1] = indices[0];
+
+                assert!(!p_o_w
+                    .verify_nonce(&challenge, &Proof::Solution { nonce, indices })
+                    .unwrap());
+
+                return ();
+            }
+
+            #[test]
+            fn unordered_subtrees_are_rejected() -> () {
+                let (mut p_o_w, challenge, nonce, indices) = solve();
+
+                // The XOR of the swapped subtrees is the same, but the leftmost indices are not ordered.
+                let half = indices.len() / 2;
+
+                let mut swapped_indices = indices[half..].to_vec();
+
+                swapped_indices.extend_from_slice(&indices[..half]);
+
+                assert!(!p_o_w
+                    .verify_nonce(
+                        &challenge,
+                        &Proof::Solution {
+                            nonce: nonce.clone(),
+                            indices: swapped_indices,
+                        },
+                    )
+                    .unwrap());
+
+                let mut swapped_leaves = indices;
+
+                swapped_leaves.swap(0, 1);
+
+                assert!(!p_o_w
+                    .verify_nonce(
+                        &challenge,
+                        &Proof::Solution {
+                            nonce,
+                            indices: swapped_leaves,
+                        },
+                    )
+                    .unwrap());
+
+                return ();
+            }
+
+            #[test]
+            fn wrong_xor_is_rejected() -> () {
+                let (mut p_o_w, challenge, nonce, mut indices) = solve();
+
+                // The last leaf is replaced by the other one, that keeps the order of the indices.
+                let last = indices.len() - 1;
+
+                indices[last] = (indices[last - 1] + 1..PARAMETERS.leaves_quantity())
+                    .find(|index| !indices.contains(index))
+                    .unwrap();
+
+                assert!(!p_o_w
+                    .verify_nonce(&challenge, &Proof::Solution { nonce, indices })
+                    .unwrap());
+
+                return ();
+            }
+
+            #[test]
+            fn parameters_exhausting_memory_are_rejected() -> () {
+                assert_eq!(
+                    size_of::<EquihashNode>() as u64,
+                    GeneralizedBirthdayParameters::NODE_BYTES_QUANTITY
+                );
+
+                assert!(GeneralizedBirthdayParameters::DEFAULT.validate().is_ok());
+
+                assert!(GeneralizedBirthdayParameters {
+                    bits_quantity: 200,
+                    rounds_quantity: 9,
+                }
+                .validate()
+                .is_err());
+
+                assert!(GeneralizedBirthdayParameters {
+                    bits_quantity: 60,
+                    rounds_quantity: 2,
+                }
+                .validate()
+                .is_err());
+
+                return ();
+            }
+        }
+    }
+}
+
+// The Hashcash v1 stamps: ver:bits:date:resource:ext:rand:counter, which SHA-1 has the bits leading zero bits.
+mod hashcash {
+    use super::Difficulty;
+    use super::ErrorKind;
+    use crypto::digest::Digest;
+    use crypto::sha1::Sha1;
+    use rand::thread_rng;
+    use rand::Rng;
+    use std::collections::HashMap;
+    use std::error::Error;
+    use std::fmt::Display;
+    use std::fmt::Formatter;
+    use std::fmt::Result as FmtResult;
+    use std::str::FromStr;
+    use std::time::Duration;
+    use std::time::SystemTime;
+    use std::time::UNIX_EPOCH;
+
+    const BASE64_ALPHABET: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Stamp {
+        pub bits: Difficulty,
+        pub date: StampDate,
+        pub resource: String,
+        pub extension: String,
+        pub random: String,
+        pub counter: String,
+    }
+
+    impl Stamp {
+        pub const VERSION: u8 = 1;
+
+        fn is_base64<'a>(value: &'a str) -> bool {
+            return !value.is_empty()
+                && value
+                    .bytes()
+                    .all(|byte| BASE64_ALPHABET.contains(&byte) || byte == b'=');
+        }
+    }
+
+    impl FromStr for Stamp {
+        type Err = ErrorKind;
+
+        fn from_str(stamp: &str) -> Result<Self, Self::Err> {
+            let fields: Vec<&str> = stamp.split(':').collect();
+
+            if fields.len() != 7 || fields[0] != Self::VERSION.to_string() {
+                return Err(ErrorKind::Logic);
+            }
+
+            let bits = match fields[1].parse::<u8>() {
+                Ok(zero_bits) => Difficulty::try_from(zero_bits)?,
+                Err(_) => {
+                    return Err(ErrorKind::Logic);
+                }
+            };
+
+            if fields[3].is_empty() || !Self::is_base64(fields[5]) || !Self::is_base64(fields[6]) {
+                return Err(ErrorKind::Logic);
+            }
+
+            return Ok(Self {
+                bits,
+                date: StampDate::from_str(fields[2])?,
+                resource: fields[3].to_string(),
+                extension: fields[4].to_string(),
+                random: fields[5].to_string(),
+                counter: fields[6].to_string(),
+            });
+        }
+    }
+
+    impl Display for Stamp {
+        fn fmt<'a>(&'a self, formatter: &'a mut Formatter<'_>) -> FmtResult {
+            write!(
+                formatter,
+                "{}:{}:{}:{}:{}:{}:{}",
+                Self::VERSION,
+                self.bits.zero_bits(),
+                &self.date,
+                &self.resource,
+                &self.extension,
+                &self.random,
+                &self.counter
+            )
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum DatePrecision {
+        // YYMMDD.
+        Day,
+        // YYMMDDhhmm.
+        Minute,
+        // YYMMDDhhmmss.
+        Second,
+    }
+
+    impl DatePrecision {
+        fn duration<'a>(&'a self) -> Duration {
+            return match *self {
+                Self::Day => Duration::from_secs(StampDate::DAY_SECONDS_QUANTITY),
+                Self::Minute => Duration::from_secs(60),
+                Self::Second => Duration::from_secs(1),
+            };
+        }
+    }
+
+    // The UTC date of the stamp. The two digit years are of the 2000-2099 range.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct StampDate {
+        // The seconds since the Unix epoch, truncated to the precision.
+        timestamp: u64,
+        precision: DatePrecision,
+    }
+
+    impl StampDate {
+        const DAY_SECONDS_QUANTITY: u64 = 24 * 60 * 60;
+        // 2000-01-01 and 2100-01-01.
+        const MINIMUM_TIMESTAMP: u64 = 946_684_800;
+        const MAXIMUM_TIMESTAMP: u64 = 4_102_444_800;
+
+        pub fn new(
+            time: SystemTime,
+            precision: DatePrecision,
+        ) -> Result<Self, Box<dyn Error + 'static>> {
+            let timestamp = match time.duration_since(UNIX_EPOCH) {
+                Ok(duration) => duration.as_secs(),
+                Err(error) => {
+                    return Err(Box::from(error));
+                }
+            };
+
+            if !(Self::MINIMUM_TIMESTAMP..Self::MAXIMUM_TIMESTAMP).contains(&timestamp) {
+                return Err(Box::from(ErrorKind::Logic));
+            }
+
+            let precision_seconds_quantity = precision.duration().as_secs();
+
+            return Ok(Self {
+                timestamp: timestamp - timestamp % precision_seconds_quantity,
+                precision,
+            });
+        }
+
+        pub fn now(precision: DatePrecision) -> Result<Self, Box<dyn Error + 'static>> {
+            return Self::new(SystemTime::now(), precision);
+        }
+
+        pub fn precision<'a>(&'a self) -> DatePrecision {
+            return self.precision;
+        }
+
+        pub fn start<'a>(&'a self) -> SystemTime {
+            return UNIX_EPOCH + Duration::from_secs(self.timestamp);
+        }
+
+        // The date covers the whole day or minute of the lower precision.
+        pub fn end<'a>(&'a self) -> SystemTime {
+            return self.start() + self.precision.duration();
+        }
+
+        // The proleptic Gregorian calendar.
+        fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+            let year_ = if month <= 2 { year - 1 } else { year };
+
+            let era = year_ / 400;
+
+            let year_of_era = year_ - era * 400;
+
+            let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+
+            let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+            return era * 146_097 + day_of_era - 719_468;
+        }
+
+        fn civil_from_days(days: u64) -> (u64, u64, u64) {
+            let days_ = days + 719_468;
+
+            let era = days_ / 146_097;
+
+            let day_of_era = days_ - era * 146_097;
+
+            let year_of_era =
+                (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+
+            let day_of_year =
+                day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+
+            let month_ = (5 * day_of_year + 2) / 153;
+
+            let day = day_of_year - (153 * month_ + 2) / 5 + 1;
+
+            let month = if month_ < 10 { month_ + 3 } else { month_ - 9 };
+
+            let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+            return (year, month, day);
+        }
+    }
+
+    impl FromStr for StampDate {
+        type Err = ErrorKind;
+
+        fn from_str(date: &str) -> Result<Self, Self::Err> {
+            let precision = match date.len() {
+                6 => DatePrecision::Day,
+                10 => DatePrecision::Minute,
+                12 => DatePrecision::Second,
+                _ => {
+                    return Err(ErrorKind::Logic);
+                }
+            };
+
+            if !date.bytes().all(|byte| byte.is_ascii_digit()) {
+                return Err(ErrorKind::Logic);
+            }
+
+            let mut values: [u64; 6] = [0; 6];
+
+            for (index, value) in values.iter_mut().enumerate().take(date.len() / 2) {
+                *value = match date[(index * 2)..(index * 2 + 2)].parse::<u64>() {
+                    Ok(value_) => value_,
+                    Err(_) => {
+                        return Err(ErrorKind::Logic);
+                    }
+                };
+            }
+
+            let [year, month, day, hour, minute, second] = values;
+
+            if !(1..=12).contains(&month) || day == 0 || hour >= 24 || minute >= 60 || second >= 60
+            {
+                return Err(ErrorKind::Logic);
+            }
+
+            let days = Self::days_from_civil(2000 + year, month, day);
+
+            // The day of the other month is normalized by the calendar.
+            if Self::civil_from_days(days) != (2000 + year, month, day) {
+                return Err(ErrorKind::Logic);
+            }
+
+            return Ok(Self {
+                timestamp: days * Self::DAY_SECONDS_QUANTITY + hour * 3600 + minute * 60 + second,
+                precision,
+            });
+        }
+    }
+
+    impl Display for StampDate {
+        fn fmt<'a>(&'a self, formatter: &'a mut Formatter<'_>) -> FmtResult {
+            let (year, month, day) =
+                Self::civil_from_days(self.timestamp / Self::DAY_SECONDS_QUANTITY);
+
+            let seconds_quantity = self.timestamp % Self::DAY_SECONDS_QUANTITY;
+
+            write!(formatter, "{:02}{:02}{:02}", year % 100, month, day)?;
+
+            match self.precision {
+                DatePrecision::Day => {}
+                DatePrecision::Minute => {
+                    write!(
+                        formatter,
+                        "{:02}{:02}",
+                        seconds_quantity / 3600,
+                        (seconds_quantity % 3600) / 60
+                    )?;
+                }
+                DatePrecision::Second => {
+                    write!(
+                        formatter,
+                        "{:02}{:02}{:02}",
+                        seconds_quantity / 3600,
+                        (seconds_quantity % 3600) / 60,
+                        seconds_quantity % 60
+                    )?;
+                }
+            }
+
+            return Ok(());
+        }
+    }
+
+    pub struct Minter;
+
+    impl Minter {
+        const RANDOM_LENGTH: usize = 16;
+
+        // Searches the counter, that gives the SHA-1 of the stamp with the bits leading zero bits.
+        pub fn mint<'a>(
+            resource: &'a str,
+            extension: &'a str,
+            bits: Difficulty,
+        ) -> Result<Stamp, Box<dyn Error + 'static>> {
+            if resource.is_empty() || resource.contains(':') || extension.contains(':') {
+                return Err(Box::from(ErrorKind::Logic));
+            }
+
+            let mut random_number_generator = thread_rng();
+
+            let random: String = (0..Self::RANDOM_LENGTH)
+                .map(|_| BASE64_ALPHABET[random_number_generator.gen_range(0..64)] as char)
+                .collect();
+
+            let mut stamp = Stamp {
+                bits,
+                date: StampDate::now(DatePrecision::Second)?,
+                resource: resource.to_string(),
+                extension: extension.to_string(),
+                random,
+                counter: String::new(),
+            };
+
+            // The stamp without the counter.
+            let mut buffer = stamp.to_string().into_bytes();
+
+            let prefix_length = buffer.len();
+
+            let mut digest = Sha1::new();
+
+            let mut hash = [0; 20];
+
+            let mut counter: u64 = 0;
+
+            'a: loop {
+                buffer.truncate(prefix_length);
+
+                Self::encode_counter(counter, &mut buffer);
+
+                digest.reset();
+
+                digest.input(buffer.as_slice());
+
+                digest.result(hash.as_mut_slice());
+
+                if bits.is_satisfied_by(hash.as_slice()) {
+                    break 'a;
+                }
+
+                counter += 1;
+            }
+
+            stamp.counter = match String::from_utf8(buffer.split_off(prefix_length)) {
+                Ok(counter_) => counter_,
+                Err(error) => {
+                    return Err(Box::from(error));
+                }
+            };
+
+            return Ok(stamp);
+        }
+
+        // The base64 digits of the counter.
+        fn encode_counter<'a>(counter: u64, buffer: &'a mut Vec<u8>) -> () {
+            let start = buffer.len();
+
+            let mut counter_ = counter;
+
+            'a: loop {
+                buffer.push(BASE64_ALPHABET[(counter_ % 64) as usize]);
+
+                counter_ /= 64;
+
+                if counter_ == 0 {
+                    break 'a;
+                }
+            }
+
+            buffer[start..].reverse();
+
+            return ();
+        }
+    }
+
+    // The hook for the database of the spent stamps, that protects from the double spending.
+    pub trait SpentStamps {
+        // Records the stamp until the expiration. Is false, if the stamp has been already spent.
+        fn spend<'a>(
+            &'a mut self,
+            stamp: &'a str,
+            expiration: SystemTime,
+        ) -> Result<bool, Box<dyn Error + 'static>>;
+    }
+
+    // Keeps the spent stamps in memory until the expiration.
+    pub struct MemorySpentStamps {
+        stamps: HashMap<String, SystemTime>,
+    }
+
+    impl MemorySpentStamps {
+        pub fn new() -> Self {
+            return Self {
+                stamps: HashMap::new(),
+            };
+        }
+    }
+
+    impl SpentStamps for MemorySpentStamps {
+        fn spend<'a>(
+            &'a mut self,
+            stamp: &'a str,
+            expiration: SystemTime,
+        ) -> Result<bool, Box<dyn Error + 'static>> {
+            let now = SystemTime::now();
+
+            self.stamps.retain(|_, expiration_| *expiration_ > now);
+
+            if self.stamps.contains_key(stamp) {
+                return Ok(false);
+            }
+
+            self.stamps.insert(stamp.to_string(), expiration);
+
+            return Ok(true);
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum StampStatus {
+        Valid,
+        Malformed,
+        UnexpectedResource,
+        InsufficientBits,
+        Expired,
+        FromFuture,
+        InsufficientWork,
+        DoubleSpent,
+    }
+
+    pub struct Verifier<S>
+    where
+        S: SpentStamps,
+    {
+        pub resource: String,
+        // The minimal quantity of bits.
+        pub bits: Difficulty,
+        // The stamp is accepted during the validity after its date.
+        pub validity: Duration,
+        // The stamp is accepted before its date by the clock skew.
+        pub clock_skew: Duration,
+        pub spent_stamps: S,
+    }
+
+    impl<S> Verifier<S>
+    where
+        S: SpentStamps,
+    {
+        // The defaults of the hashcash tool.
+        pub const DEFAULT_VALIDITY: Duration =
+            Duration::from_secs(28 * StampDate::DAY_SECONDS_QUANTITY);
+        pub const DEFAULT_CLOCK_SKEW: Duration =
+            Duration::from_secs(2 * StampDate::DAY_SECONDS_QUANTITY);
+
+        pub fn new(resource: String, bits: Difficulty, spent_stamps: S) -> Self {
+            return Self {
+                resource,
+                bits,
+                validity: Self::DEFAULT_VALIDITY,
+                clock_skew: Self::DEFAULT_CLOCK_SKEW,
+                spent_stamps,
+            };
+        }
+
+        pub fn verify<'a>(
+            &'a mut self,
+            stamp: &'a str,
+        ) -> Result<StampStatus, Box<dyn Error + 'static>> {
+            return self.verify_at(stamp, SystemTime::now());
+        }
+
+        // The valid stamp is spent, so, it is not valid anymore.
+        pub fn verify_at<'a>(
+            &'a mut self,
+            stamp: &'a str,
+            now: SystemTime,
+        ) -> Result<StampStatus, Box<dyn Error + 'static>> {
+            let stamp_ = match Stamp::from_str(stamp) {
+                Ok(stamp__) => stamp__,
+                Err(_) => {
+                    return Ok(StampStatus::Malformed);
+                }
+            };
+
+            if stamp_.resource != self.resource {
+                return Ok(StampStatus::UnexpectedResource);
+            }
+
+            if stamp_.bits < self.bits {
+                return Ok(StampStatus::InsufficientBits);
+            }
+
+            let expiration = match stamp_.date.end().checked_add(self.validity) {
+                Some(expiration_) => expiration_,
+                None => {
+                    return Err(Box::from(ErrorKind::Logic));
+                }
+            };
+
+            if now >= expiration {
+                return Ok(StampStatus::Expired);
+            }
+
+            match now.checked_add(self.clock_skew) {
+                Some(latest) => {
+                    if stamp_.date.start() > latest {
+                        return Ok(StampStatus::FromFuture);
+                    }
+                }
+                None => {
+                    return Err(Box::from(ErrorKind::Logic));
+                }
+            }
+
+            // The stamp is hashed as it is received.
+            let mut digest = Sha1::new();
+
+            let mut hash = [0; 20];
+
+            digest.input(stamp.as_bytes());
+
+            digest.result(hash.as_mut_slice());
+
+            if !stamp_.bits.is_satisfied_by(hash.as_slice()) {
+                return Ok(StampStatus::InsufficientWork);
+            }
+
+            if !self.spent_stamps.spend(stamp, expiration)? {
+                return Ok(StampStatus::DoubleSpent);
+            }
+
+            return Ok(StampStatus::Valid);
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::super::Difficulty;
+        use super::DatePrecision;
+        use super::MemorySpentStamps;
+        use super::Minter;
+        use super::StampDate;
+        use super::StampStatus;
+        use super::Verifier;
+        use std::str::FromStr;
+        use std::time::Duration;
+
+        const RESOURCE: &str = "resource";
+
+        fn verifier() -> Verifier<MemorySpentStamps> {
+            return Verifier::new(
+                RESOURCE.to_string(),
+                Difficulty::I,
+                MemorySpentStamps::new(),
+            );
+        }
+
+        #[test]
+        fn leap_day_is_read_in_leap_years_only() -> () {
+            for date in ["240229", "000229", "960229", "2402291200"].iter() {
+                assert_eq!(StampDate::from_str(date).unwrap().to_string(), *date);
+            }
+
+            for date in ["230229", "240230", "990229"].iter() {
+                assert!(StampDate::from_str(date).is_err());
+            }
+
+            return ();
+        }
+
+        #[test]
+        fn date_crosses_month_and_year_boundaries() -> () {
+            let date = StampDate::from_str("231231235959").unwrap();
+
+            assert_eq!(
+                StampDate::new(date.end(), DatePrecision::Second)
+                    .unwrap()
+                    .to_string(),
+                "240101000000"
+            );
+
+            let date_ = StampDate::from_str("240131").unwrap();
+
+            assert_eq!(
+                StampDate::new(date_.end(), DatePrecision::Day)
+                    .unwrap()
+                    .to_string(),
+                "240201"
+            );
+
+            assert_eq!(
+                date_.end().duration_since(date_.start()).unwrap(),
+                Duration::from_secs(24 * 60 * 60)
+            );
+
+            for date__ in ["230132", "230431", "231301", "230001", "230100"].iter() {
+                assert!(StampDate::from_str(date__).is_err());
+            }
+
+            return ();
+        }
+
+        #[test]
+        fn malformed_stamp_is_rejected() -> () {
+            let mut verifier_ = verifier();
+
+            for stamp in [
+                "",
+                "0:8:240101:resource::AAAA:AA",
+                "1:8:240101:resource::AAAA",
+                "1:8:240101:resource::AAAA:AA:AA",
+                "1:x:240101:resource::AAAA:AA",
+                "1:0:240101:resource::AAAA:AA",
+                "1:65:240101:resource::AAAA:AA",
+                "1:8:2401011:resource::AAAA:AA",
+                "1:8:24010124:resource::AAAA:AA",
+                "1:8:2401012400:resource::AAAA:AA",
+                "1:8:24o101:resource::AAAA:AA",
+                "1:8:240101:::AAAA:AA",
+                "1:8:240101:resource::AA-A:AA",
+                "1:8:240101:resource::AAAA:",
+            ]
+            .iter()
+            {
+                assert_eq!(verifier_.verify(stamp).unwrap(), StampStatus::Malformed);
+            }
+
+            return ();
+        }
+
+        #[test]
+        fn expired_stamp_is_rejected() -> () {
+            let stamp = Minter::mint(RESOURCE, "", Difficulty::I).unwrap();
+
+            let mut verifier_ = verifier();
+
+            let expiration = stamp.date.end() + verifier_.validity;
+
+            assert_eq!(
+                verifier_
+                    .verify_at(stamp.to_string().as_str(), expiration)
+                    .unwrap(),
+                StampStatus::Expired
+            );
+
+            assert_eq!(
+                verifier_
+                    .verify_at(
+                        stamp.to_string().as_str(),
+                        stamp.date.start() - verifier_.clock_skew - Duration::from_secs(1)
+                    )
+                    .unwrap(),
+                StampStatus::FromFuture
+            );
+
+            assert_eq!(
+                verifier_
+                    .verify_at(
+                        stamp.to_string().as_str(),
+                        expiration - Duration::from_secs(1)
+                    )
+                    .unwrap(),
+                StampStatus::Valid
+            );
+
+            return ();
+        }
+
+        #[test]
+        fn replayed_stamp_is_rejected() -> () {
+            let stamp = Minter::mint(RESOURCE, "extension", Difficulty::I)
+                .unwrap()
+                .to_string();
+
+            let mut verifier_ = verifier();
+
+            assert_eq!(
+                verifier_.verify(stamp.as_str()).unwrap(),
+                StampStatus::Valid
+            );
+
+            assert_eq!(
+                verifier_.verify(stamp.as_str()).unwrap(),
+                StampStatus::DoubleSpent
+            );
+
+            assert_eq!(
+                Verifier::new("other".to_string(), Difficulty::I, MemorySpentStamps::new())
+                    .verify(stamp.as_str())
+                    .unwrap(),
+                StampStatus::UnexpectedResource
+            );
+
+            return ();
+        }
+    }
+}
+
+mod access_token {
+    use crypto::hmac::Hmac;
+    use crypto::mac::Mac;
+    use crypto::mac::MacResult;
+    use crypto::sha2::Sha256;
+    use rand::thread_rng;
+    use rand::Rng;
+    use serde::Deserialize;
+    use serde::Serialize;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use std::sync::MutexGuard;
+    use std::sync::PoisonError;
+    use std::time::Duration;
+    use std::time::SystemTime;
+    use std::time::UNIX_EPOCH;
+    use uuid::Uuid;
+
+    // Allows the requests without the new work until the expiration, but no more, than the quantity of the requests.
+    // The token is kept by the client, and the server checks the signature, so, it keeps only the spent quantities.
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct AccessToken {
+        pub id: Uuid,
+        // In seconds since the unix epoch.
+        pub expiration: u64,
+        pub requests_quantity: u32,
+        pub signature: Vec<u8>,
+    }
+
+    impl AccessToken {
+        pub fn is_expired_at<'a>(&'a self, now: SystemTime) -> bool {
+            return unix_seconds(now) >= self.expiration;
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum AccessTokenStatus {
+        Valid,
+        Forged,
+        Expired,
+        Exhausted,
+    }
+
+    // Signs the access tokens by the HMAC-SHA256 and counts their spent requests.
+    pub struct AccessTokenIssuer {
+        key: Vec<u8>,
+        pub lifetime: Duration,
+        pub requests_quantity: u32,
+        // The expiration and the quantity of the spent requests of the token.
+        spent_requests: Mutex<HashMap<Uuid, (u64, u32)>>,
+    }
+
+    impl AccessTokenIssuer {
+        pub const KEY_LENGTH: usize = 32;
+        pub const DEFAULT_LIFETIME: Duration = Duration::from_secs(60);
+        pub const DEFAULT_REQUESTS_QUANTITY: u32 = 10;
+
+        // The tokens, that are signed by the same key, are accepted after the restart, but their spent requests are forgotten.
+        pub fn new(key: Vec<u8>, lifetime: Duration, requests_quantity: u32) -> Self {
+            return Self {
+                key,
+                lifetime,
+                requests_quantity,
+                spent_requests: Mutex::new(HashMap::new()),
+            };
+        }
+
+        pub fn generate_key() -> Vec<u8> {
+            let mut random_number_generator = thread_rng();
+
+            return (0..Self::KEY_LENGTH)
+                .map(|_| random_number_generator.gen::<u8>())
+                .collect();
+        }
+
+        pub fn issue<'a>(&'a self) -> AccessToken {
+            return self.issue_at(SystemTime::now());
+        }
+
+        // The token is issued for the redeemed challenge only, so, the spent requests of the expired tokens
+        // are forgotten here and the quantity of the kept ones is bounded by the work of the clients.
+        pub fn issue_at<'a>(&'a self, now: SystemTime) -> AccessToken {
+            let now_ = unix_seconds(now);
+
+            self.lock_spent_requests()
+                .retain(|_, (expiration, _)| *expiration > now_);
+
+            let id = Uuid::new_v4();
+
+            let expiration = now_.saturating_add(self.lifetime.as_secs());
+
+            return AccessToken {
+                id,
+                expiration,
+                requests_quantity: self.requests_quantity,
+                signature: self.sign(&id, expiration, self.requests_quantity),
+            };
+        }
+
+        pub fn spend<'a>(&'a self, access_token: &'a AccessToken) -> AccessTokenStatus {
+            return self.spend_at(access_token, SystemTime::now());
+        }
+
+        pub fn spend_at<'a>(
+            &'a self,
+            access_token: &'a AccessToken,
+            now: SystemTime,
+        ) -> AccessTokenStatus {
+            let signature = self.sign(
+                &access_token.id,
+                access_token.expiration,
+                access_token.requests_quantity,
+            );
+
+            // The comparison takes the same time for any signature.
+            if MacResult::new_from_owned(signature)
+                != MacResult::new(access_token.signature.as_slice())
+            {
+                return AccessTokenStatus::Forged;
+            }
+
+            if access_token.is_expired_at(now) {
+                return AccessTokenStatus::Expired;
+            }
+
+            let mut spent_requests = self.lock_spent_requests();
+
+            let (_, spent_requests_quantity) = spent_requests
+                .entry(access_token.id)
+                .or_insert((access_token.expiration, 0));
+
+            if *spent_requests_quantity >= access_token.requests_quantity {
+                return AccessTokenStatus::Exhausted;
+            }
+
+            *spent_requests_quantity += 1;
+
+            return AccessTokenStatus::Valid;
+        }
+
+        // Returns back the spent request, that is not served.
+        pub fn refund<'a>(&'a self, access_token: &'a AccessToken) -> () {
+            if let Some((_, spent_requests_quantity)) =
+                self.lock_spent_requests().get_mut(&access_token.id)
+            {
+                *spent_requests_quantity = spent_requests_quantity.saturating_sub(1);
+            }
+
+            return ();
+        }
+
+        fn sign<'a>(&'a self, id: &'a Uuid, expiration: u64, requests_quantity: u32) -> Vec<u8> {
+            let mut hmac = Hmac::new(Sha256::new(), self.key.as_slice());
+
+            hmac.input(id.as_bytes());
+
+            hmac.input(expiration.to_be_bytes().as_slice());
+
+            hmac.input(requests_quantity.to_be_bytes().as_slice());
+
+            return hmac.result().code().to_vec();
+        }
+
+        fn lock_spent_requests<'a>(&'a self) -> MutexGuard<'a, HashMap<Uuid, (u64, u32)>> {
+            return self
+                .spent_requests
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    fn unix_seconds(time: SystemTime) -> u64 {
+        return time
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+    }
+}
+
+mod challenge_table {
+    use super::Challenge;
+    use std::collections::hash_map::Entry;
+    use std::collections::hash_map::RandomState;
+    use std::collections::BTreeSet;
+    use std::collections::HashMap;
+    use std::hash::BuildHasher;
+    use std::net::IpAddr;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+    use std::sync::Mutex;
+    use std::sync::MutexGuard;
+    use std::sync::PoisonError;
+    use std::time::Duration;
+    use std::time::SystemTime;
+    use uuid::Uuid;
+
+    #[derive(Debug, Clone)]
+    pub struct IssuedChallenge {
+        pub challenge: Challenge,
+        // The challenge is not redeemed after it.
+        pub expiration: SystemTime,
+        // The client, which quantity of the outstanding challenges is limited. Is absent for the challenge,
+        // that is not limited per client.
+        pub client: Option<IpAddr>,
+    }
+
+    impl IssuedChallenge {
+        pub fn is_expired_at<'a>(&'a self, now: SystemTime) -> bool {
+            return self.expiration <= now;
+        }
+    }
+
+    // The issued challenges of the tokens. The tokens are split between the shards by the randomly keyed hash,
+    // so, the concurrent requests of the different tokens rarely wait for the same lock, even if the tokens are chosen
+    // by the client. Every lock is held only for the single operation of the map, that can not leave the shard
+    // inconsistent, so, the lock, that is poisoned by the panic of another thread, is recovered.
+    // The challenge expires after the lifetime, and the expired challenges of the shard are evicted by the insertion
+    // into it, so, the table does not grow with the challenges, that are never redeemed. The lock of the shard
+    // is taken before the lock of the clients.
+    pub struct ChallengeTable {
+        shards: Vec<Mutex<Shard>>,
+        random_state: RandomState,
+        challenges_quantity: AtomicUsize,
+        lifetime: Duration,
+        maximum_client_challenges_quantity: usize,
+        // The expirations of the outstanding challenges per client. The expired challenges of the client are
+        // forgotten here before its limit is checked, so, they do not count against it, even if they are not evicted
+        // from the shards yet.
+        clients: Mutex<HashMap<IpAddr, BTreeSet<(SystemTime, Uuid)>>>,
+    }
+
+    impl ChallengeTable {
+        pub const DEFAULT_SHARDS_QUANTITY: usize = 64;
+        pub const DEFAULT_LIFETIME: Duration = Duration::from_secs(600);
+        pub const DEFAULT_MAXIMUM_CLIENT_CHALLENGES_QUANTITY: usize = 64;
+
+        pub fn new(
+            shards_quantity: usize,
+            lifetime: Duration,
+            maximum_client_challenges_quantity: usize,
+        ) -> Self {
+            let now = SystemTime::now();
+
+            return Self {
+                shards: (0..shards_quantity.max(1))
+                    .map(|_| {
+                        return Mutex::new(Shard {
+                            challenges: HashMap::new(),
+                            last_sweep: now,
+                        });
+                    })
+                    .collect(),
+                random_state: RandomState::new(),
+                challenges_quantity: AtomicUsize::new(0),
+                lifetime,
+                maximum_client_challenges_quantity,
+                clients: Mutex::new(HashMap::new()),
+            };
+        }
+
+        pub fn lifetime<'a>(&'a self) -> Duration {
+            return self.lifetime;
+        }
+
+        // Replaces the challenge, that is issued to the token before. The challenge is not limited per client.
+        pub fn insert<'a>(&'a self, token: Uuid, challenge: Challenge) -> () {
+            let issued_challenge = IssuedChallenge {
+                challenge,
+                expiration: SystemTime::now() + self.lifetime,
+                client: None,
+            };
+
+            let mut shard = self.shard(&token);
+
+            self.insert_(&mut shard, token, issued_challenge);
+
+            return ();
+        }
+
+        // Is false, if the client has the maximum quantity of the outstanding challenges, so, the challenge is not issued.
+        // The limit is checked and the challenge is counted under the same lock, so, the concurrent requests
+        // of the client do not exceed it.
+        pub fn insert_for<'a>(&'a self, client: IpAddr, token: Uuid, challenge: Challenge) -> bool {
+            let now = SystemTime::now();
+
+            let issued_challenge = IssuedChallenge {
+                challenge,
+                expiration: now + self.lifetime,
+                client: Some(client),
+            };
+
+            let mut shard = self.shard(&token);
+
+            {
+                let mut clients = self.lock_clients();
+
+                let expirations = clients.entry(client).or_default();
+
+                'a: while let Some(&(expiration, _)) = expirations.first() {
+                    if expiration > now {
+                        break 'a;
+                    }
+
+                    expirations.pop_first();
+                }
+
+                if expirations.len() >= self.maximum_client_challenges_quantity {
+                    if expirations.is_empty() {
+                        clients.remove(&client);
+                    }
+
+                    return false;
+                }
+
+                expirations.insert((issued_challenge.expiration, token));
+            }
+
+            self.insert_(&mut shard, token, issued_challenge);
+
+            return true;
+        }
+
+        // Is whether the client has the maximum quantity of the outstanding challenges, that are not expired,
+        // so, the refused request does not need the challenge to be generated. The insertion checks it again.
+        pub fn is_client_limited<'a>(&'a self, client: IpAddr) -> bool {
+            let now = SystemTime::now();
+
+            return self.lock_clients().get(&client).is_some_and(|expirations| {
+                return expirations
+                    .iter()
+                    .filter(|(expiration, _)| *expiration > now)
+                    .count()
+                    >= self.maximum_client_challenges_quantity;
+            });
+        }
+
+        // Removes the challenge, so, only one of the concurrent redemptions of the token gets it.
+        // The expired challenge is removed, but is not returned.
+        pub fn take<'a>(&'a self, token: &'a Uuid) -> Option<IssuedChallenge> {
+            let mut shard = self.shard(token);
+
+            let issued_challenge = shard.challenges.remove(token)?;
+
+            self.forget(token, &issued_challenge);
+
+            if issued_challenge.is_expired_at(SystemTime::now()) {
+                return None;
+            }
+
+            return Some(issued_challenge);
+        }
+
+        // Returns the taken challenge back with its expiration, unless it is expired or the new challenge
+        // is issued to the token meanwhile.
+        pub fn restore<'a>(&'a self, token: Uuid, issued_challenge: IssuedChallenge) -> () {
+            if issued_challenge.is_expired_at(SystemTime::now()) {
+                return ();
+            }
+
+            let mut shard = self.shard(&token);
+
+            if let Entry::Vacant(entry) = shard.challenges.entry(token) {
+                if let Some(client) = issued_challenge.client {
+                    self.lock_clients()
+                        .entry(client)
+                        .or_default()
+                        .insert((issued_challenge.expiration, token));
+                }
+
+                entry.insert(issued_challenge);
+
+                self.challenges_quantity.fetch_add(1, Ordering::Relaxed);
+            }
+
+            return ();
+        }
+
+        // Evicts the expired challenges of every shard, and is the quantity of the evicted challenges.
+        pub fn evict_expired<'a>(&'a self) -> usize {
+            let now = SystemTime::now();
+
+            let quantity = self.len();
+
+            for shard in self.shards.iter() {
+                self.sweep(
+                    &mut shard.lock().unwrap_or_else(PoisonError::into_inner),
+                    now,
+                );
+            }
+
+            return quantity.saturating_sub(self.len());
+        }
+
+        pub fn len<'a>(&'a self) -> usize {
+            return self.challenges_quantity.load(Ordering::Relaxed);
+        }
+
+        pub fn is_empty<'a>(&'a self) -> bool {
+            return self.len() == 0;
+        }
+
+        // The copy of the challenges, that are issued at the moment, for the persistent storage.
+        // Every shard is copied under its own lock, so, the copy is consistent per shard only.
+        pub fn snapshot<'a>(&'a self) -> Vec<(Uuid, IssuedChallenge)> {
+            let now = SystemTime::now();
+
+            let mut challenges: Vec<(Uuid, IssuedChallenge)> = Vec::with_capacity(self.len());
+
+            for shard in self.shards.iter() {
+                challenges.extend(
+                    shard
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .challenges
+                        .iter()
+                        .filter(|(_, issued_challenge)| !issued_challenge.is_expired_at(now))
+                        .map(|(token, issued_challenge)| (*token, issued_challenge.clone())),
+                );
+            }
+
+            return challenges;
+        }
+
+        // The quantity is changed under the lock of the shard, so, it never falls below zero.
+        // The challenge of the client is counted by the caller.
+        fn insert_<'a>(
+            &'a self,
+            shard: &'a mut Shard,
+            token: Uuid,
+            issued_challenge: IssuedChallenge,
+        ) -> () {
+            let now = SystemTime::now();
+
+            // The expired challenges are looked for once per the lifetime, so, the insertion does not scan
+            // the shard every time.
+            if now
+                .duration_since(shard.last_sweep)
+                .is_ok_and(|elapsed| elapsed >= self.lifetime)
+            {
+                self.sweep(shard, now);
+            }
+
+            let key = (issued_challenge.client, issued_challenge.expiration);
+
+            match shard.challenges.insert(token, issued_challenge) {
+                Some(replaced_challenge) => {
+                    // The replaced challenge with the same expiration of the same client is counted once.
+                    if (replaced_challenge.client, replaced_challenge.expiration) != key {
+                        self.release(&token, &replaced_challenge);
+                    }
+                }
+                None => {
+                    self.challenges_quantity.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+
+            return ();
+        }
+
+        fn sweep<'a>(&'a self, shard: &'a mut Shard, now: SystemTime) -> () {
+            shard.last_sweep = now;
+
+            let mut expired_challenges: Vec<(Uuid, IssuedChallenge)> = vec![];
+
+            shard.challenges.retain(|token, issued_challenge| {
+                if issued_challenge.is_expired_at(now) {
+                    expired_challenges.push((*token, issued_challenge.clone()));
+
+                    return false;
+                }
+
+                return true;
+            });
+
+            for (token, expired_challenge) in expired_challenges.iter() {
+                self.forget(token, expired_challenge);
+            }
+
+            return ();
+        }
+
+        fn forget<'a>(&'a self, token: &'a Uuid, issued_challenge: &'a IssuedChallenge) -> () {
+            self.challenges_quantity.fetch_sub(1, Ordering::Relaxed);
+
+            self.release(token, issued_challenge);
+
+            return ();
+        }
+
+        // The expired challenge may be forgotten by the client already.
+        fn release<'a>(&'a self, token: &'a Uuid, issued_challenge: &'a IssuedChallenge) -> () {
+            if let Some(client) = issued_challenge.client {
+                let mut clients = self.lock_clients();
+
+                if let Entry::Occupied(mut entry) = clients.entry(client) {
+                    entry
+                        .get_mut()
+                        .remove(&(issued_challenge.expiration, *token));
+
+                    if entry.get().is_empty() {
+                        entry.remove();
+                    }
+                }
+            }
+
+            return ();
+        }
+
+        fn shard<'a>(&'a self, token: &'a Uuid) -> MutexGuard<'a, Shard> {
+            let index = (self.random_state.hash_one(token) as usize) % self.shards.len();
+
+            return self.shards[index]
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+
+        fn lock_clients<'a>(
+            &'a self,
+        ) -> MutexGuard<'a, HashMap<IpAddr, BTreeSet<(SystemTime, Uuid)>>> {
+            return self.clients.lock().unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    impl Default for ChallengeTable {
+        fn default() -> Self {
+            return Self::new(
+                Self::DEFAULT_SHARDS_QUANTITY,
+                Self::DEFAULT_LIFETIME,
+                Self::DEFAULT_MAXIMUM_CLIENT_CHALLENGES_QUANTITY,
+            );
+        }
+    }
+
+    struct Shard {
+        challenges: HashMap<Uuid, IssuedChallenge>,
+        last_sweep: SystemTime,
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::super::Challenge;
+        use super::super::Difficulty;
+        use super::super::POW;
+        use super::ChallengeTable;
+        use std::net::IpAddr;
+        use std::net::Ipv4Addr;
+        use std::thread::scope;
+        use std::thread::sleep;
+        use std::time::Duration;
+        use uuid::Uuid;
+
+        const CLIENT: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+        fn challenge() -> Challenge {
+            return POW::new(POW::DEFAULT_ALGORITHM)
+                .unwrap()
+                .generate_challenge(Difficulty::I);
+        }
+
+        #[test]
+        fn challenge_is_taken_once() {
+            let challenge_table = ChallengeTable::default();
+
+            let token = Uuid::new_v4();
+
+            challenge_table.insert(token, challenge());
+
+            assert!(challenge_table.take(&token).is_some());
+
+            assert!(challenge_table.take(&token).is_none());
+
+            assert!(challenge_table.is_empty());
+        }
+
+        #[test]
+        fn expired_challenge_is_not_taken() {
+            let challenge_table = ChallengeTable::new(1, Duration::from_millis(10), 1);
+
+            let token = Uuid::new_v4();
+
+            assert!(challenge_table.insert_for(CLIENT, token, challenge()));
+
+            sleep(Duration::from_millis(20));
+
+            assert!(challenge_table.take(&token).is_none());
+
+            // The expired challenge does not count against the client.
+            assert!(challenge_table.insert_for(CLIENT, Uuid::new_v4(), challenge()));
+        }
+
+        #[test]
+        fn expired_challenges_are_evicted() {
+            let challenge_table = ChallengeTable::new(4, Duration::from_millis(10), 2);
+
+            assert!(challenge_table.insert_for(CLIENT, Uuid::new_v4(), challenge()));
+
+            assert!(challenge_table.insert_for(CLIENT, Uuid::new_v4(), challenge()));
+
+            challenge_table.insert(Uuid::new_v4(), challenge());
+
+            sleep(Duration::from_millis(20));
+
+            assert_eq!(challenge_table.evict_expired(), 3);
+
+            assert!(challenge_table.is_empty());
+
+            assert!(challenge_table.snapshot().is_empty());
+
+            assert!(challenge_table.insert_for(CLIENT, Uuid::new_v4(), challenge()));
+        }
+
+        #[test]
+        fn client_challenges_are_limited() {
+            let challenge_table = ChallengeTable::new(4, Duration::from_secs(60), 2);
+
+            let token = Uuid::new_v4();
+
+            assert!(challenge_table.insert_for(CLIENT, token, challenge()));
+
+            assert!(challenge_table.insert_for(CLIENT, Uuid::new_v4(), challenge()));
+
+            assert!(!challenge_table.insert_for(CLIENT, Uuid::new_v4(), challenge()));
+
+            // The other client is not limited by the first one.
+            assert!(challenge_table.insert_for(
+                IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)),
+                Uuid::new_v4(),
+                challenge()
+            ));
+
+            let issued_challenge = challenge_table.take(&token).unwrap();
+
+            assert!(challenge_table.insert_for(CLIENT, Uuid::new_v4(), challenge()));
+
+            // The restored challenge is counted again, but is not refused.
+            challenge_table.restore(token, issued_challenge);
+
+            assert_eq!(challenge_table.len(), 4);
+
+            assert!(!challenge_table.insert_for(CLIENT, Uuid::new_v4(), challenge()));
+        }
+
+        #[test]
+        fn expired_challenges_free_client_slots() {
+            let challenge_table = ChallengeTable::new(1, Duration::from_millis(50), 4);
+
+            for _ in 0..4 {
+                assert!(challenge_table.insert_for(CLIENT, Uuid::new_v4(), challenge()));
+            }
+
+            assert!(!challenge_table.insert_for(CLIENT, Uuid::new_v4(), challenge()));
+
+            sleep(Duration::from_millis(100));
+
+            // The expired challenges are neither redeemed nor evicted.
+            for _ in 0..4 {
+                assert!(challenge_table.insert_for(CLIENT, Uuid::new_v4(), challenge()));
+            }
+
+            assert!(!challenge_table.insert_for(CLIENT, Uuid::new_v4(), challenge()));
+        }
+
+        #[test]
+        fn concurrent_client_challenges_do_not_exceed_limit() {
+            let challenge_table = ChallengeTable::new(
+                ChallengeTable::DEFAULT_SHARDS_QUANTITY,
+                Duration::from_secs(60),
+                16,
+            );
+
+            let issued_quantity = scope(|scope_| {
+                let handles: Vec<_> = (0..8)
+                    .map(|_| {
+                        return scope_.spawn(|| {
+                            return (0..16)
+                                .filter(|_| {
+                                    return challenge_table.insert_for(
+                                        CLIENT,
+                                        Uuid::new_v4(),
+                                        challenge(),
+                                    );
+                                })
+                                .count();
+                        });
+                    })
+                    .collect();
+
+                return handles
+                    .into_iter()
+                    .map(|handle| handle.join().unwrap())
+                    .sum::<usize>();
+            });
+
+            assert_eq!(issued_quantity, 16);
+
+            assert_eq!(challenge_table.len(), 16);
+        }
+
+        #[test]
+        fn replaced_challenge_frees_client_slot() {
+            let challenge_table = ChallengeTable::new(1, Duration::from_secs(60), 2);
+
+            let token = Uuid::new_v4();
+
+            assert!(challenge_table.insert_for(CLIENT, token, challenge()));
+
+            assert!(challenge_table.insert_for(CLIENT, Uuid::new_v4(), challenge()));
+
+            // The challenge of the token is replaced, so, the client keeps two challenges.
+            challenge_table.insert(token, challenge());
+
+            assert!(challenge_table.insert_for(CLIENT, Uuid::new_v4(), challenge()));
+
+            assert!(!challenge_table.insert_for(CLIENT, Uuid::new_v4(), challenge()));
+        }
+    }
+}
+
+#[cfg(feature = "tower")]
+mod http_gate {
+    use super::Algorithm;
+    use super::ChallengeTable;
+    use super::Difficulty;
+    use super::Parameters;
+    use super::ProofPayload;
+    use super::Serializer;
+    use super::POW;
+    use http::header::HeaderValue;
+    use http::header::WWW_AUTHENTICATE;
+    use http::Extensions;
+    use http::HeaderMap;
+    use http::Request;
+    use http::Response;
+    use http::StatusCode;
+    use std::future::Future;
+    use std::mem::replace;
+    use std::net::IpAddr;
+    use std::net::SocketAddr;
+    use std::pin::Pin;
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use std::sync::PoisonError;
+    use std::task::Context;
+    use std::task::Poll;
+    use std::time::Duration;
+    use std::time::Instant;
+    use tokio::task::spawn_blocking;
+    use tower_layer::Layer;
+    use tower_service::Service;
+    use tracing::debug;
+    use tracing::error;
+    use tracing::field::display;
+    use tracing::field::Empty;
+    use tracing::info_span;
+    use tracing::Instrument;
+    use tracing::Span;
+    use uuid::Uuid;
+
+    pub struct ProofOfWorkLayerConfig {
+        pub algorithm: Algorithm,
+        pub parameters: Parameters,
+        // The difficulty is split between the puzzles.
+        pub puzzles_quantity: u16,
+        // The difficulty of the paths, that do not start with any of the prefixes of the routes.
+        pub difficulty: Difficulty,
+        // The prefixes of the paths with their difficulties, the longest matching prefix is chosen.
+        pub routes: Vec<(String, Difficulty)>,
+        // The requests are answered with 503, while the quantity of the issued challenges, that are not expired,
+        // reaches it.
+        pub maximum_challenges_quantity: usize,
+        // The address of the client of the request, so, its outstanding challenges are limited by the challenge table
+        // before the total quantity, and the single client does not fill the table for everyone.
+        // The challenges of the request without the address are limited by the total quantity only.
+        pub client_of: for<'a> fn(&'a HeaderMap, &'a Extensions) -> Option<IpAddr>,
+    }
+
+    impl ProofOfWorkLayerConfig {
+        pub const DEFAULT_MAXIMUM_CHALLENGES_QUANTITY: usize = 100000;
+
+        pub fn new(difficulty: Difficulty) -> Self {
+            return Self {
+                algorithm: POW::DEFAULT_ALGORITHM,
+                parameters: Parameters::default_for(POW::DEFAULT_ALGORITHM),
+                puzzles_quantity: 1,
+                difficulty,
+                routes: vec![],
+                maximum_challenges_quantity: Self::DEFAULT_MAXIMUM_CHALLENGES_QUANTITY,
+                client_of: Self::connection_client,
+            };
+        }
+
+        // The address of the connection, that is inserted into the extensions of the request by the server,
+        // as the `SocketAddr` or the `IpAddr`. The address of the proxy is configured by the custom function instead.
+        pub fn connection_client<'a>(
+            _headers: &'a HeaderMap,
+            extensions: &'a Extensions,
+        ) -> Option<IpAddr> {
+            return extensions
+                .get::<SocketAddr>()
+                .map(SocketAddr::ip)
+                .or_else(|| extensions.get::<IpAddr>().copied());
+        }
+
+        pub fn difficulty_of<'a>(&'a self, path: &'a str) -> Difficulty {
+            return self
+                .routes
+                .iter()
+                .filter(|(prefix, _)| path.starts_with(prefix.as_str()))
+                .max_by_key(|(prefix, _)| prefix.len())
+                .map_or(self.difficulty, |(_, difficulty)| *difficulty);
+        }
+    }
+
+    // Gates the HTTP service: the request without the valid proof is answered with 401 and the challenge
+    // in the `Proof-Of-Work-Challenge` header (`<token> <hex of the challenge>`), the request with the proof
+    // in the `Proof-Of-Work` header (`<token> <hex of the proof payload>`) is passed to the service.
+    // The challenge and the proof payload are serialized as in the TCP protocol.
+    // The challenges expire with the lifetime of the challenge table.
+    #[derive(Clone)]
+    pub struct ProofOfWorkLayer {
+        config: Arc<ProofOfWorkLayerConfig>,
+        challenge_table: Arc<ChallengeTable>,
+        // Of the full table, so, the refused requests do not sweep it every time.
+        last_eviction: Arc<Mutex<Option<Instant>>>,
+    }
+
+    impl ProofOfWorkLayer {
+        pub const CHALLENGE_HEADER: &'static str = "proof-of-work-challenge";
+        pub const PROOF_HEADER: &'static str = "proof-of-work";
+        pub const AUTHENTICATION_SCHEME: &'static str = "ProofOfWork";
+        const EVICTION_INTERVAL: Duration = Duration::from_secs(1);
+
+        // The challenge table can be shared with the other gates.
+        pub fn new(config: ProofOfWorkLayerConfig, challenge_table: Arc<ChallengeTable>) -> Self {
+            return Self {
+                config: Arc::new(config),
+                challenge_table,
+                last_eviction: Arc::new(Mutex::new(None)),
+            };
+        }
+
+        // The expired challenges are counted, until they are swept, so, the full table is swept before the refusal,
+        // but not more often, than once per the eviction interval or the lifetime of the challenges.
+        // Is whether the table is not full anymore.
+        fn evict_expired<'a>(&'a self) -> bool {
+            {
+                let mut last_eviction = self
+                    .last_eviction
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner);
+
+                let eviction_interval =
+                    Self::EVICTION_INTERVAL.min(self.challenge_table.lifetime());
+
+                if last_eviction
+                    .is_some_and(|last_eviction_| last_eviction_.elapsed() < eviction_interval)
+                {
+                    return false;
+                }
+
+                *last_eviction = Some(Instant::now());
+            }
+
+            self.challenge_table.evict_expired();
+
+            return self.challenge_table.len() < self.config.maximum_challenges_quantity;
+        }
+
+        pub fn format_header<'a>(token: &'a Uuid, data: &'a [u8]) -> String {
+            let mut value = format!("{} ", token);
+
+            for byte in data.iter() {
+                value.push_str(format!("{:02x}", byte).as_str());
+            }
+
+            return value;
+        }
+
+        // Is None for the malformed header.
+        pub fn parse_header<'a>(value: &'a str) -> Option<(Uuid, Vec<u8>)> {
+            let (token, hex) = value.trim().split_once(' ')?;
+
+            let token_ = Uuid::from_str(token).ok()?;
+
+            if hex.len() % 2 != 0 {
+                return None;
+            }
+
+            let mut data: Vec<u8> = Vec::with_capacity(hex.len() / 2);
+
+            for index in (0..hex.len()).step_by(2) {
+                data.push(u8::from_str_radix(hex.get(index..index + 2)?, 16).ok()?);
+            }
+
+            return Some((token_, data));
+        }
+    }
+
+    impl<S> Layer<S> for ProofOfWorkLayer {
+        type Service = ProofOfWorkService<S>;
+
+        fn layer<'a>(&'a self, inner: S) -> Self::Service {
+            return ProofOfWorkService {
+                inner,
+                layer: self.clone(),
+            };
+        }
+    }
+
+    #[derive(Clone)]
+    pub struct ProofOfWorkService<S> {
+        inner: S,
+        layer: ProofOfWorkLayer,
+    }
+
+    impl<S> ProofOfWorkService<S> {
+        // The checked challenge is spent, the challenge of the other route is returned back.
+        async fn redeem(
+            layer: ProofOfWorkLayer,
+            header: Option<HeaderValue>,
+            difficulty: Difficulty,
+        ) -> bool {
+            let (token, data) = match header
+                .as_ref()
+                .and_then(|header_| header_.to_str().ok())
+                .and_then(ProofOfWorkLayer::parse_header)
+            {
+                Some(parsed) => parsed,
+                None => {
+                    if header.is_some() {
+                        debug!("The proof header is malformed.");
+                    }
+
+                    return false;
+                }
+            };
+
+            Span::current().record("token", display(&token));
+
+            let proof_payload = match Serializer::deserialize::<'_, ProofPayload>(data.as_slice()) {
+                Ok(proof_payload_) => proof_payload_,
+                Err(error) => {
+                    debug!("{}", &error);
+
+                    return false;
+                }
+            };
+
+            let issued_challenge = match layer.challenge_table.take(&token) {
+                Some(issued_challenge_) => issued_challenge_,
+                None => {
+                    debug!("The challenge is not issued or is already redeemed.");
+
+                    return false;
+                }
+            };
+
+            // The challenge of the cheaper route does not open the more expensive one.
+            if issued_challenge.challenge.difficulty
+                < difficulty.split(layer.config.puzzles_quantity)
+            {
+                debug!(
+                    "The challenge of {} zero bits does not open the route.",
+                    issued_challenge.challenge.difficulty.zero_bits()
+                );
+
+                layer.challenge_table.restore(token, issued_challenge);
+
+                return false;
+            }
+
+            let challenge_ = issued_challenge.challenge;
+
+            // The verification is CPU-bound, so, it does not block the async executor.
+            let is_verified = spawn_blocking(move || {
+                return match POW::new(challenge_.algorithm) {
+                    Ok(mut p_o_w) => matches!(p_o_w.verify(&challenge_, &proof_payload), Ok(true)),
+                    Err(_) => false,
+                };
+            })
+            .await
+            .unwrap_or(false);
+
+            // The challenge is spent by the failed check too, so, every verification costs the client the new work.
+            if !is_verified {
+                debug!("The proof is not verified.");
+            }
+
+            return is_verified;
+        }
+
+        fn challenge<'a, B>(
+            layer: &'a ProofOfWorkLayer,
+            client: Option<IpAddr>,
+            difficulty: Difficulty,
+        ) -> Response<B>
+        where
+            B: Default,
+        {
+            if client.is_some_and(|client_| layer.challenge_table.is_client_limited(client_)) {
+                return Self::status(StatusCode::TOO_MANY_REQUESTS);
+            }
+
+            if layer.challenge_table.len() >= layer.config.maximum_challenges_quantity
+                && !layer.evict_expired()
+            {
+                return Self::status(StatusCode::SERVICE_UNAVAILABLE);
+            }
+
+            let challenge = match POW::new_with_parameters(
+                layer.config.algorithm,
+                layer.config.parameters.clone(),
+            )
+            .and_then(|p_o_w| {
+                return p_o_w.generate_multi_puzzle_challenge(
+                    difficulty.split(layer.config.puzzles_quantity),
+                    layer.config.puzzles_quantity,
+                );
+            }) {
+                Ok(challenge_) => challenge_,
+                Err(error) => {
+                    error!("{}", &error);
+
+                    return Self::status(StatusCode::INTERNAL_SERVER_ERROR);
+                }
+            };
+
+            let data = match Serializer::serialize(&challenge) {
+                Ok(data_) => data_,
+                Err(error) => {
+                    error!("{}", &error);
+
+                    return Self::status(StatusCode::INTERNAL_SERVER_ERROR);
+                }
+            };
+
+            let token = Uuid::new_v4();
+
+            let header = match HeaderValue::from_str(
+                ProofOfWorkLayer::format_header(&token, data.as_slice()).as_str(),
+            ) {
+                Ok(header_) => header_,
+                Err(error) => {
+                    error!("{}", &error);
+
+                    return Self::status(StatusCode::INTERNAL_SERVER_ERROR);
+                }
+            };
+
+            match client {
+                Some(client_) => {
+                    if !layer.challenge_table.insert_for(client_, token, challenge) {
+                        return Self::status(StatusCode::TOO_MANY_REQUESTS);
+                    }
+                }
+                None => {
+                    layer.challenge_table.insert(token, challenge);
+                }
+            }
+
+            let mut response = Self::status(StatusCode::UNAUTHORIZED);
+
+            response.headers_mut().insert(
+                WWW_AUTHENTICATE,
+                HeaderValue::from_static(ProofOfWorkLayer::AUTHENTICATION_SCHEME),
+            );
+
+            response
+                .headers_mut()
+                .insert(ProofOfWorkLayer::CHALLENGE_HEADER, header);
+
+            return response;
+        }
+
+        fn status<B>(status: StatusCode) -> Response<B>
+        where
+            B: Default,
+        {
+            let mut response = Response::new(B::default());
+
+            *response.status_mut() = status;
+
+            return response;
+        }
+    }
+
+    impl<S, RequestBody, ResponseBody> Service<Request<RequestBody>> for ProofOfWorkService<S>
+    where
+        S: Service<Request<RequestBody>, Response = Response<ResponseBody>>
+            + Clone
+            + Send
+            + 'static,
+        S::Future: Send + 'static,
+        RequestBody: Send + 'static,
+        ResponseBody: Default + 'static,
+    {
+        type Response = Response<ResponseBody>;
+        type Error = S::Error;
+        type Future =
+            Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+        fn poll_ready<'a>(
+            &'a mut self,
+            context: &'a mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            return self.inner.poll_ready(context);
+        }
+
+        fn call<'a>(&'a mut self, request: Request<RequestBody>) -> Self::Future {
+            // The service, that is polled ready, handles the request, and its clone waits for the next one.
+            let inner_ = self.inner.clone();
+
+            let mut inner = replace(&mut self.inner, inner_);
+
+            let layer = self.layer.clone();
+
+            let difficulty = layer.config.difficulty_of(request.uri().path());
+
+            let header = request
+                .headers()
+                .get(ProofOfWorkLayer::PROOF_HEADER)
+                .cloned();
+
+            let client = (layer.config.client_of)(request.headers(), request.extensions());
+
+            let span = info_span!(
+                "proof_of_work",
+                path = request.uri().path(),
+                difficulty = difficulty.zero_bits(),
+                token = Empty,
+                outcome = Empty
+            );
+
+            return Box::pin(
+                async move {
+                    if !Self::redeem(layer.clone(), header, difficulty).await {
+                        let response = Self::challenge(&layer, client, difficulty);
+
+                        let outcome = match response.status() {
+                            StatusCode::UNAUTHORIZED => "challenged",
+                            StatusCode::TOO_MANY_REQUESTS => "limited",
+                            StatusCode::SERVICE_UNAVAILABLE => "unavailable",
+                            _ => "failed",
+                        };
+
+                        Span::current().record("outcome", outcome);
+
+                        debug!("The request is not passed.");
+
+                        return Ok(response);
+                    }
+
+                    Span::current().record("outcome", "passed");
+
+                    debug!("The request is passed.");
+
+                    return inner.call(request).await;
+                }
+                .instrument(span),
+            );
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::super::ChallengeTable;
+        use super::super::Difficulty;
+        use super::super::Proof;
+        use super::super::ProofPayload;
+        use super::super::Serializer;
+        use super::super::POW;
+        use super::ProofOfWorkLayer;
+        use super::ProofOfWorkLayerConfig;
+        use super::ProofOfWorkService;
+        use http::header::HeaderValue;
+        use http::Extensions;
+        use http::HeaderMap;
+        use http::StatusCode;
+        use std::net::IpAddr;
+        use std::net::Ipv4Addr;
+        use std::net::SocketAddr;
+        use std::sync::Arc;
+        use std::thread::sleep;
+        use std::time::Duration;
+        use uuid::Uuid;
+
+        fn layer(lifetime: Duration, maximum_challenges_quantity: usize) -> ProofOfWorkLayer {
+            let mut config = ProofOfWorkLayerConfig::new(Difficulty::I);
+
+            config.maximum_challenges_quantity = maximum_challenges_quantity;
+
+            return ProofOfWorkLayer::new(config, Arc::new(ChallengeTable::new(1, lifetime, 1)));
+        }
+
+        fn header<'a>(token: &'a Uuid, proof_payload: &'a ProofPayload) -> Option<HeaderValue> {
+            let data = Serializer::serialize(proof_payload).unwrap();
+
+            return Some(
+                HeaderValue::from_str(
+                    ProofOfWorkLayer::format_header(token, data.as_slice()).as_str(),
+                )
+                .unwrap(),
+            );
+        }
+
+        #[test]
+        fn expired_challenges_do_not_refuse_requests() -> () {
+            let layer_ = layer(Duration::from_millis(10), 1);
+
+            let status = |layer__: &ProofOfWorkLayer| {
+                return ProofOfWorkService::<()>::challenge::<()>(layer__, None, Difficulty::I)
+                    .status();
+            };
+
+            assert_eq!(status(&layer_), StatusCode::UNAUTHORIZED);
+
+            assert_eq!(status(&layer_), StatusCode::SERVICE_UNAVAILABLE);
+
+            sleep(Duration::from_millis(20));
+
+            // The expired challenge is still counted, until the full table is swept.
+            assert_eq!(layer_.challenge_table.len(), 1);
+
+            assert_eq!(status(&layer_), StatusCode::UNAUTHORIZED);
+
+            return ();
+        }
+
+        #[test]
+        fn client_is_limited_before_total_quantity() -> () {
+            let mut config = ProofOfWorkLayerConfig::new(Difficulty::I);
+
+            config.maximum_challenges_quantity = 3;
+
+            let layer_ = ProofOfWorkLayer::new(
+                config,
+                Arc::new(ChallengeTable::new(1, Duration::from_secs(60), 2)),
+            );
+
+            let status = |client: Option<IpAddr>| {
+                return ProofOfWorkService::<()>::challenge::<()>(&layer_, client, Difficulty::I)
+                    .status();
+            };
+
+            let client = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+
+            assert_eq!(status(Some(client)), StatusCode::UNAUTHORIZED);
+
+            assert_eq!(status(Some(client)), StatusCode::UNAUTHORIZED);
+
+            // The client at its limit does not fill the table for the other ones.
+            assert_eq!(status(Some(client)), StatusCode::TOO_MANY_REQUESTS);
+
+            assert_eq!(layer_.challenge_table.len(), 2);
+
+            assert_eq!(
+                status(Some(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)))),
+                StatusCode::UNAUTHORIZED
+            );
+
+            // The limit of the client is checked before the full table.
+            assert_eq!(status(Some(client)), StatusCode::TOO_MANY_REQUESTS);
+
+            assert_eq!(status(None), StatusCode::SERVICE_UNAVAILABLE);
+
+            return ();
+        }
+
+        #[test]
+        fn client_is_taken_from_connection() -> () {
+            let mut extensions = Extensions::new();
+
+            assert_eq!(
+                ProofOfWorkLayerConfig::connection_client(&HeaderMap::new(), &extensions),
+                None
+            );
+
+            extensions.insert(SocketAddr::from((Ipv4Addr::new(127, 0, 0, 3), 8080)));
+
+            assert_eq!(
+                ProofOfWorkLayerConfig::connection_client(&HeaderMap::new(), &extensions),
+                Some(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 3)))
+            );
+
+            return ();
+        }
+
+        #[tokio::test]
+        async fn challenge_is_spent_by_failed_proof() -> () {
+            let layer_ = layer(Duration::from_secs(60), 1);
+
+            let response = ProofOfWorkService::<()>::challenge::<()>(&layer_, None, Difficulty::I);
+
+            let (token, data) = ProofOfWorkLayer::parse_header(
+                response.headers()[ProofOfWorkLayer::CHALLENGE_HEADER]
+                    .to_str()
+                    .unwrap(),
+            )
+            .unwrap();
+
+            let challenge = Serializer::deserialize(data.as_slice()).unwrap();
+
+            let mut p_o_w = POW::new(POW::DEFAULT_ALGORITHM).unwrap();
+
+            let proof_payload = p_o_w.solve(&challenge).unwrap();
+
+            let mut nonce: u64 = 0;
+
+            let wrong_proof_payload = 'a: loop {
+                let proof_payload_ = ProofPayload::encode(
+                    POW::DEFAULT_ALGORITHM,
+                    &Proof::Nonce(nonce.to_be_bytes().to_vec()),
+                )
+                .unwrap();
+
+                if !p_o_w.verify(&challenge, &proof_payload_).unwrap() {
+                    break 'a proof_payload_;
+                }
+
+                nonce += 1;
+            };
+
+            assert!(
+                !ProofOfWorkService::<()>::redeem(
+                    layer_.clone(),
+                    header(&token, &wrong_proof_payload),
+                    Difficulty::I
+                )
+                .await
+            );
+
+            assert!(
+                !ProofOfWorkService::<()>::redeem(
+                    layer_,
+                    header(&token, &proof_payload),
+                    Difficulty::I
+                )
+                .await
+            );
+
+            return ();
+        }
+    }
+}
+
+mod logging {
+    use std::env::var;
+    use std::env::VarError;
+    use std::error::Error;
+    use std::io::stderr;
+    use std::io::stdout;
+    use std::io::IsTerminal;
+    use std::str::FromStr;
+    use tracing_subscriber::fmt::layer;
+    use tracing_subscriber::fmt::writer::BoxMakeWriter;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::reload::Handle;
+    use tracing_subscriber::reload::Layer as ReloadLayer;
+    use tracing_subscriber::util::SubscriberInitExt;
+    use tracing_subscriber::EnvFilter;
+    use tracing_subscriber::Registry;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum LogFormat {
+        // The event per line.
+        Text,
+        // The event with its fields and spans on the several lines.
+        Pretty,
+        // The event as the JSON object per line.
+        Json,
+    }
+
+    impl FromStr for LogFormat {
+        type Err = Box<dyn Error + 'static>;
+
+        fn from_str<'a>(value: &'a str) -> Result<Self, Self::Err> {
+            return match value {
+                "text" => Ok(Self::Text),
+                "pretty" => Ok(Self::Pretty),
+                "json" => Ok(Self::Json),
+                _ => Err(Box::from(format!(
+                    "The {} log format is unknown, the text, pretty or json is expected.",
+                    value
+                ))),
+            };
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum LogDestination {
+        Stdout,
+        // Keeps the stdout for the output of the program.
+        Stderr,
+    }
+
+    // Keeps the filter of the installed subscriber, so, the filter is replaced without the restart.
+    #[derive(Clone)]
+    pub struct Logger {
+        filter: Handle<EnvFilter, Registry>,
+    }
+
+    impl Logger {
+        pub const FORMAT_VARIABLE: &'static str = "LOG_FORMAT";
+        // The filter has the syntax of the `RUST_LOG` directives, as `info,infrastructure=debug`.
+        pub const FILTER_VARIABLE: &'static str = "LOG_FILTER";
+
+        // The format and the filter are read from the environment, so, they are chosen on the start
+        // without the rebuild.
+        pub fn initialize<'a>(
+            default_filter: &'a str,
+            destination: LogDestination,
+        ) -> Result<Self, Box<dyn Error + 'static>> {
+            let format = match Self::read(Self::FORMAT_VARIABLE)? {
+                Some(format_) => LogFormat::from_str(format_.as_str())?,
+                None => LogFormat::Text,
+            };
+
+            let filter = match Self::read(Self::FILTER_VARIABLE)? {
+                Some(filter_) => filter_,
+                None => String::from(default_filter),
+            };
+
+            return Self::initialize_with(format, filter.as_str(), destination);
+        }
+
+        pub fn initialize_with<'a>(
+            format: LogFormat,
+            filter: &'a str,
+            destination: LogDestination,
+        ) -> Result<Self, Box<dyn Error + 'static>> {
+            let (filter_layer, filter_handle) = ReloadLayer::new(Self::parse(filter)?);
+
+            // The colors are written only to the terminal, so, the redirected log is not polluted.
+            let (writer, is_terminal) = match destination {
+                LogDestination::Stdout => (BoxMakeWriter::new(stdout), stdout().is_terminal()),
+                LogDestination::Stderr => (BoxMakeWriter::new(stderr), stderr().is_terminal()),
+            };
+
+            let subscriber = Registry::default().with(filter_layer);
+
+            let format_layer = layer().with_writer(writer).with_ansi(is_terminal);
+
+            let result = match format {
+                LogFormat::Text => subscriber.with(format_layer).try_init(),
+                LogFormat::Pretty => subscriber.with(format_layer.pretty()).try_init(),
+                LogFormat::Json => subscriber.with(format_layer.json()).try_init(),
+            };
+
+            if let Err(error) = result {
+                return Err(Box::from(error));
+            }
+
+            return Ok(Self {
+                filter: filter_handle,
+            });
+        }
+
+        // The invalid filter is rejected, and the current one is kept.
+        pub fn reload<'a>(&'a self, filter: &'a str) -> Result<(), Box<dyn Error + 'static>> {
+            if let Err(error) = self.filter.reload(Self::parse(filter)?) {
+                return Err(Box::from(error));
+            }
+
+            return Ok(());
+        }
+
+        fn parse<'a>(filter: &'a str) -> Result<EnvFilter, Box<dyn Error + 'static>> {
+            return match EnvFilter::try_new(filter) {
+                Ok(env_filter) => Ok(env_filter),
+                Err(error) => Err(Box::from(error)),
+            };
+        }
+
+        fn read(name: &'static str) -> Result<Option<String>, Box<dyn Error + 'static>> {
+            return match var(name) {
+                Ok(value) => Ok(Some(value)),
+                Err(VarError::NotPresent) => Ok(None),
+                Err(error) => Err(Box::from(error)),
+            };
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::LogDestination;
+        use super::LogFormat;
+        use super::Logger;
+        use tracing::enabled;
+        use tracing::Level;
+
+        // The subscriber is installed once per process, so, the single test covers the reloading.
+        #[test]
+        fn filter_is_reloaded() -> () {
+            let logger =
+                Logger::initialize_with(LogFormat::Text, "warn", LogDestination::Stderr).unwrap();
+
+            assert!(!enabled!(Level::DEBUG));
+
+            logger.reload("debug").unwrap();
+
+            assert!(enabled!(Level::DEBUG));
+
+            assert!(logger.reload("debug,=").is_err());
+
+            assert!(enabled!(Level::DEBUG));
+
+            logger.reload("warn").unwrap();
+
+            assert!(!enabled!(Level::DEBUG));
+
+            return ();
+        }
     }
 }
 
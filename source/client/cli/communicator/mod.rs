@@ -71,31 +71,128 @@
     clippy::zero_sized_map_values
 )]
 
+use infrastructure::tokio::io::copy_bidirectional;
+use infrastructure::tokio::net::TcpListener;
 use infrastructure::tokio::net::TcpStream;
 use infrastructure::tokio::runtime::Builder;
+use infrastructure::tokio::signal::ctrl_c;
+use infrastructure::tokio::spawn;
+use infrastructure::tokio::sync::mpsc::channel;
+use infrastructure::tracing::debug;
+use infrastructure::tracing::error;
+use infrastructure::tracing::info;
+use infrastructure::tracing::info_span;
+use infrastructure::tracing::warn;
+use infrastructure::tracing::Instrument;
 use infrastructure::uuid::Uuid;
+use infrastructure::AccessToken;
+use infrastructure::Admission;
+use infrastructure::Algorithm;
+use infrastructure::Calibration;
+use infrastructure::CancellationToken;
+use infrastructure::Challenge;
+use infrastructure::Difficulty;
 use infrastructure::ErrorKind;
+use infrastructure::Estimate;
+use infrastructure::LogDestination;
+use infrastructure::Logger;
+use infrastructure::Payload;
+use infrastructure::Progress;
+use infrastructure::ProofPayload;
 use infrastructure::Protocol;
+use infrastructure::QuoteFilter;
 use infrastructure::Request;
 use infrastructure::Response;
-use infrastructure::Secret;
 use infrastructure::Serializer;
+use infrastructure::Solver;
 use infrastructure::WordOfWisdom;
 use infrastructure::POW;
 use infrastructure::SERVER_SOCKET_ADDRESS;
 use std::borrow::Cow;
 use std::convert::From;
+use std::env::args;
+use std::env::var_os;
 use std::error::Error;
+use std::fs::read;
+use std::fs::remove_file;
+use std::fs::DirBuilder;
+use std::fs::OpenOptions;
+use std::fs::Permissions;
+use std::io::stdout;
+use std::io::Write;
+use std::os::unix::fs::DirBuilderExt;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+const USAGE: &str = "Usage: communicator [[--deadline <seconds>] [--tag <tag>] [--language <language>] [--maximum-length <characters>] | bench [--algorithm <name>] [--duration <seconds>] [--target <seconds>] | tunnel --listen <address>]";
 
 fn main() -> () {
-    if let Err(error) = process() {
+    // The log is written to the stderr, so, the stdout keeps only the payload.
+    if let Err(error) = Logger::initialize("info", LogDestination::Stderr) {
         println!("{}", &error);
+
+        return ();
+    }
+
+    if let Err(error) = process() {
+        error!("{}", &error);
     }
 
     return ();
 }
 
 fn process() -> Result<(), Box<dyn Error + 'static>> {
+    let arguments: Vec<String> = args().skip(1).collect();
+
+    return match arguments.first().map(String::as_str) {
+        Some("bench") => bench(&arguments[1..]),
+        Some("tunnel") => tunnel(&arguments[1..]),
+        _ => run_communication(arguments.as_slice()),
+    };
+}
+
+fn run_communication<'a>(arguments: &'a [String]) -> Result<(), Box<dyn Error + 'static>> {
+    let mut deadline: Option<Duration> = None;
+
+    let mut filter = QuoteFilter::default();
+
+    let mut arguments_ = arguments.iter();
+
+    'a: loop {
+        let (argument, value) = match (arguments_.next(), arguments_.next()) {
+            (Some(argument_), Some(value_)) => (argument_.as_str(), value_.as_str()),
+            (None, _) => {
+                break 'a;
+            }
+            (Some(_), None) => {
+                return Err(Box::from(USAGE));
+            }
+        };
+
+        match (argument, value.parse::<u32>()) {
+            ("--deadline", Ok(seconds)) => {
+                deadline = Some(Duration::from_secs(seconds as u64));
+            }
+            ("--tag", _) => {
+                filter.tag = Some(String::from(value));
+            }
+            ("--language", _) => {
+                filter.language = Some(String::from(value));
+            }
+            ("--maximum-length", Ok(characters_quantity)) => {
+                filter.maximum_length = Some(characters_quantity);
+            }
+            _ => {
+                return Err(Box::from(USAGE));
+            }
+        }
+    }
+
     let runtime = match Builder::new_current_thread().enable_all().build() {
         Ok(runtime_) => runtime_,
         Err(error) => {
@@ -103,39 +200,257 @@ fn process() -> Result<(), Box<dyn Error + 'static>> {
         }
     };
 
-    if let Err(error) = runtime.block_on(communicate()) {
+    if let Err(error) = runtime.block_on(communicate(deadline, filter)) {
         return Err(Box::from(error));
     }
 
     return Ok(());
 }
 
-async fn communicate() -> Result<(), Box<dyn Error + 'static>> {
+async fn communicate(
+    deadline: Option<Duration>,
+    filter: QuoteFilter,
+) -> Result<(), Box<dyn Error + 'static>> {
+    // Without the cache directory the access token is not cached.
+    let access_token_path = get_access_token_path();
+
+    if let Some(access_token) = access_token_path.as_deref().and_then(load_access_token) {
+        let span = info_span!("access", token = %access_token.id);
+
+        match access_word_of_wisdom(access_token, filter.clone())
+            .instrument(span.clone())
+            .await
+        {
+            Ok(WordOfWisdom::AccessDenied) => {
+                span.in_scope(|| {
+                    debug!("The cached access token is denied, so, the new challenge is solved.");
+                });
+
+                // The cache is optional.
+                if let Some(access_token_path_) = access_token_path.as_deref() {
+                    let _ = remove_file(access_token_path_);
+                }
+            }
+            Err(error) => {
+                span.in_scope(|| {
+                    debug!("{} The new challenge is solved.", &error);
+                });
+
+                if let Some(access_token_path_) = access_token_path.as_deref() {
+                    let _ = remove_file(access_token_path_);
+                }
+            }
+            Ok(word_of_wisdom) => {
+                return handle_word_of_wisdom(word_of_wisdom, access_token_path.as_deref());
+            }
+        }
+    }
+
     let token = Uuid::new_v4();
 
-    let secret = get_secret(&token).await?;
+    let challenge = get_challenge(&token).await?;
+
+    let span = info_span!(
+        "challenge",
+        token = %token,
+        algorithm = %challenge.algorithm,
+        difficulty = challenge.difficulty.zero_bits()
+    );
 
-    let mut p_o_w = POW::new(POW::DEFAULT_DIFFICULTY);
+    let p_o_w = POW::new(challenge.algorithm)?;
 
-    let nonce = p_o_w.find_nonce(&secret)?;
+    let cancellation_token = CancellationToken::new();
 
-    let word_of_wisdom = get_word_of_wisdom(&token, nonce).await?;
+    let cancellation_token_ = cancellation_token.clone();
 
+    // The solving is aborted by the Ctrl-C.
+    spawn(async move {
+        if ctrl_c().await.is_ok() {
+            cancellation_token_.cancel();
+        }
+    });
+
+    let (progress_sender, mut progress_receiver) = channel::<Progress>(1);
+
+    spawn(
+        async move {
+            while let Some(progress) = progress_receiver.recv().await {
+                report_progress(&progress);
+            }
+        }
+        .instrument(span.clone()),
+    );
+
+    let solver = Solver {
+        cancellation_token,
+        deadline: deadline.map(|deadline_| Instant::now() + deadline_),
+        progress_sender: Some(progress_sender),
+        progress_interval: Solver::DEFAULT_PROGRESS_INTERVAL,
+    };
+
+    let started_at = Instant::now();
+
+    let proof = solver
+        .solve(p_o_w, challenge)
+        .instrument(span.clone())
+        .await?;
+
+    span.in_scope(|| {
+        info!(
+            "The challenge is solved in {}",
+            format_duration(started_at.elapsed())
+        );
+    });
+
+    let word_of_wisdom = get_word_of_wisdom(&token, proof, filter)
+        .instrument(span)
+        .await?;
+
+    return handle_word_of_wisdom(word_of_wisdom, access_token_path.as_deref());
+}
+
+fn handle_word_of_wisdom<'a>(
+    word_of_wisdom: WordOfWisdom<'a>,
+    access_token_path: Option<&'a Path>,
+) -> Result<(), Box<dyn Error + 'static>> {
     match word_of_wisdom {
-        WordOfWisdom::Result { result } => {
-            println!("{}", result);
+        WordOfWisdom::Result {
+            result,
+            access_token,
+        } => {
+            print_payload(&result)?;
+
+            if let (Some(access_token_), Some(access_token_path_)) =
+                (access_token, access_token_path)
+            {
+                // The cache is optional, so, the failure to keep the access token only costs the new work next time.
+                let _ = store_access_token(access_token_path_, &access_token_);
+            }
         }
         WordOfWisdom::Fail => {
             println!("Failed. Work proof has been corrupted.");
         }
+        WordOfWisdom::NoMatch => {
+            println!("No quote matches the filter.");
+        }
+        WordOfWisdom::AccessDenied => {
+            return Err(Box::from(ErrorKind::Logic));
+        }
     }
 
     return Ok(());
 }
 
-async fn get_secret<'a>(token: &'a Uuid) -> Result<Secret, Box<dyn Error + 'static>> {
+// The access token is cached between the runs, and is reused, until the server denies it.
+fn get_access_token_path() -> Option<PathBuf> {
+    if let Some(path) = var_os("COMMUNICATOR_ACCESS_TOKEN_PATH") {
+        return Some(PathBuf::from(path));
+    }
+
+    // The access token is a bearer credential, so, it is kept in the cache directory of the user, not in the shared temporary directory.
+    let cache_directory_path = match var_os("XDG_CACHE_HOME") {
+        Some(path) if !path.is_empty() => PathBuf::from(path),
+        _ => PathBuf::from(var_os("HOME")?).join(".cache"),
+    };
+
+    return Some(
+        cache_directory_path
+            .join("communicator")
+            .join("access_token"),
+    );
+}
+
+fn load_access_token<'a>(access_token_path: &'a Path) -> Option<AccessToken> {
+    let data = read(access_token_path).ok()?;
+
+    let access_token = Serializer::deserialize::<'_, AccessToken>(data.as_slice()).ok()?;
+
+    if access_token.is_expired_at(SystemTime::now()) {
+        return None;
+    }
+
+    return Some(access_token);
+}
+
+fn store_access_token<'a>(
+    access_token_path: &'a Path,
+    access_token: &'a AccessToken,
+) -> Result<(), Box<dyn Error + 'static>> {
+    let data = Serializer::serialize(access_token)?;
+
+    if let Some(directory_path) = access_token_path.parent() {
+        if let Err(error) = DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(directory_path)
+        {
+            return Err(Box::from(error));
+        }
+    }
+
+    // The mode is applied to the created file only, so, the permissions of the existing file are set too.
+    let mut file = match OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(access_token_path)
+    {
+        Ok(file_) => file_,
+        Err(error) => {
+            return Err(Box::from(error));
+        }
+    };
+
+    if let Err(error) = file.set_permissions(Permissions::from_mode(0o600)) {
+        return Err(Box::from(error));
+    }
+
+    if let Err(error) = file.write_all(data.as_slice()) {
+        return Err(Box::from(error));
+    }
+
+    return Ok(());
+}
+
+async fn access_word_of_wisdom(
+    access_token: AccessToken,
+    filter: QuoteFilter,
+) -> Result<WordOfWisdom<'static>, Box<dyn Error + 'static>> {
+    let request = Request::Access {
+        access_token,
+        filter,
+    };
+
+    let data = Serializer::serialize(&request)?;
+
+    let mut tcp_stream = match TcpStream::connect(SERVER_SOCKET_ADDRESS).await {
+        Ok(tcp_stream_) => tcp_stream_,
+        Err(error) => {
+            return Err(Box::from(error));
+        }
+    };
+
+    Protocol::send(&mut tcp_stream, data).await?;
+
+    let data = Protocol::receive(&mut tcp_stream).await?;
+
+    let response = Serializer::deserialize::<'_, Response>(data.as_slice())?;
+
+    let word_of_wisdom_ = match response {
+        Response::WordOfWisdom { word_of_wisdom } => word_of_wisdom,
+        _ => {
+            return Err(Box::from(ErrorKind::Logic));
+        }
+    };
+
+    return Ok(word_of_wisdom_);
+}
+
+async fn get_challenge<'a>(token: &'a Uuid) -> Result<Challenge, Box<dyn Error + 'static>> {
     let request = Request::Challenge {
         token: Cow::Borrowed(token),
+        algorithms: POW::supported_algorithms(),
     };
 
     let data = Serializer::serialize(&request)?;
@@ -153,23 +468,35 @@ async fn get_secret<'a>(token: &'a Uuid) -> Result<Secret, Box<dyn Error + 'stat
 
     let response = Serializer::deserialize::<'_, Response>(data.as_slice())?;
 
-    let secret_ = match response {
-        Response::Challenge { secret } => secret,
-        Response::WordOfWisdom { word_of_wisdom: _ } => {
+    let challenge_ = match response {
+        Response::Challenge { challenge } => challenge,
+        Response::AlgorithmMismatch { algorithms } => {
+            let names: Vec<&str> = algorithms.iter().map(Algorithm::name).collect();
+
+            return Err(Box::from(format!(
+                "The server supports only the {} algorithms.",
+                names.join(", ")
+            )));
+        }
+        Response::WordOfWisdom { word_of_wisdom: _ }
+        | Response::Admission { admission: _ }
+        | Response::Admit { access_token: _ } => {
             return Err(Box::from(ErrorKind::Logic));
         }
     };
 
-    return Ok(secret_);
+    return Ok(challenge_);
 }
 
 async fn get_word_of_wisdom<'a>(
     token: &'a Uuid,
-    nonce: u64,
+    proof: ProofPayload,
+    filter: QuoteFilter,
 ) -> Result<WordOfWisdom, Box<dyn Error + 'static>> {
     let request = Request::WordOfWisdom {
         token: Cow::Borrowed(token),
-        result: nonce,
+        proof,
+        filter,
     };
 
     let data = Serializer::serialize(&request)?;
@@ -188,7 +515,10 @@ async fn get_word_of_wisdom<'a>(
     let response = Serializer::deserialize::<'_, Response>(data.as_slice())?;
 
     let word_of_wisdom_ = match response {
-        Response::Challenge { secret: _ } => {
+        Response::Challenge { challenge: _ }
+        | Response::AlgorithmMismatch { algorithms: _ }
+        | Response::Admission { admission: _ }
+        | Response::Admit { access_token: _ } => {
             return Err(Box::from(ErrorKind::Logic));
         }
         Response::WordOfWisdom { word_of_wisdom } => word_of_wisdom,
@@ -196,3 +526,289 @@ async fn get_word_of_wisdom<'a>(
 
     return Ok(word_of_wisdom_);
 }
+
+// The binary payload is written as is, so, it can be redirected to the file.
+fn tunnel<'a>(arguments: &'a [String]) -> Result<(), Box<dyn Error + 'static>> {
+    let listen_address = match arguments {
+        [argument, value] if argument.as_str() == "--listen" => value.as_str(),
+        _ => {
+            return Err(Box::from(USAGE));
+        }
+    };
+
+    let runtime = match Builder::new_current_thread().enable_all().build() {
+        Ok(runtime_) => runtime_,
+        Err(error) => {
+            return Err(Box::from(error));
+        }
+    };
+
+    return runtime.block_on(run_tunnel(listen_address));
+}
+
+// Every local connection is admitted by the gate separately, so, it solves its own challenge
+// and is spliced through the gate connection to the upstream of the proxy.
+async fn run_tunnel<'a>(listen_address: &'a str) -> Result<(), Box<dyn Error + 'static>> {
+    let tcp_listener = match TcpListener::bind(listen_address).await {
+        Ok(tcp_listener_) => tcp_listener_,
+        Err(error) => {
+            return Err(Box::from(error));
+        }
+    };
+
+    loop {
+        let (tcp_stream, socket_address) = match tcp_listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(error) => {
+                return Err(Box::from(error));
+            }
+        };
+
+        spawn(
+            async move {
+                if let Err(error) = pass_through_gate(tcp_stream).await {
+                    warn!("{}", &error);
+                }
+            }
+            .instrument(info_span!("connection", peer = %socket_address)),
+        );
+    }
+}
+
+async fn pass_through_gate(mut tcp_stream: TcpStream) -> Result<(), Box<dyn Error + 'static>> {
+    let token = Uuid::new_v4();
+
+    let challenge = get_challenge(&token).await?;
+
+    debug!(
+        "The challenge {} of {} zero bits is received.",
+        &token,
+        challenge.difficulty.zero_bits()
+    );
+
+    let p_o_w = POW::new(challenge.algorithm)?;
+
+    let proof = Solver::new(CancellationToken::new())
+        .solve(p_o_w, challenge)
+        .await?;
+
+    let request = Request::Admission {
+        token: Cow::Borrowed(&token),
+        proof,
+        keep_connection: true,
+    };
+
+    let data = Serializer::serialize(&request)?;
+
+    let mut gate_tcp_stream = match TcpStream::connect(SERVER_SOCKET_ADDRESS).await {
+        Ok(gate_tcp_stream_) => gate_tcp_stream_,
+        Err(error) => {
+            return Err(Box::from(error));
+        }
+    };
+
+    Protocol::send(&mut gate_tcp_stream, data).await?;
+
+    let data = Protocol::receive(&mut gate_tcp_stream).await?;
+
+    match Serializer::deserialize::<'_, Response>(data.as_slice())? {
+        Response::Admission {
+            admission: Admission::Admitted,
+        } => {}
+        Response::Admission {
+            admission: Admission::Fail,
+        } => {
+            return Err(Box::from("The admission is refused."));
+        }
+        _ => {
+            return Err(Box::from(ErrorKind::Logic));
+        }
+    }
+
+    info!("The connection is admitted.");
+
+    let (sent_bytes_quantity, received_bytes_quantity) =
+        match copy_bidirectional(&mut tcp_stream, &mut gate_tcp_stream).await {
+            Ok(bytes_quantities) => bytes_quantities,
+            Err(error) => {
+                return Err(Box::from(error));
+            }
+        };
+
+    info!(
+        "The connection is closed: {} bytes are sent, {} bytes are received.",
+        sent_bytes_quantity, received_bytes_quantity
+    );
+
+    return Ok(());
+}
+
+fn print_payload<'a>(payload: &'a Payload<'a>) -> Result<(), Box<dyn Error + 'static>> {
+    match *payload {
+        Payload::Text { ref text } => {
+            println!("{}", text);
+        }
+        Payload::Binary {
+            media_type: _,
+            ref data,
+        } => {
+            if let Err(error) = stdout().write_all(data.as_ref()) {
+                return Err(Box::from(error));
+            }
+        }
+        Payload::Quote { ref quote } => {
+            println!("{}", quote.text);
+
+            let attribution: Vec<&str> = [&quote.author, &quote.source]
+                .into_iter()
+                .flatten()
+                .map(|value| value.as_ref())
+                .collect();
+
+            if !attribution.is_empty() {
+                println!("- {}", attribution.join(", "));
+            }
+        }
+    }
+
+    return Ok(());
+}
+
+fn report_progress<'a>(progress: &'a Progress) -> () {
+    let estimated_remaining_time = match progress.estimated_remaining_time {
+        Some(estimated_remaining_time_) => format_duration(estimated_remaining_time_),
+        None => String::from("unknown"),
+    };
+
+    info!(
+        "Solving: {} attempts ({} puzzles solved) in {}, {:.0} attempts per second, about {} left.",
+        progress.attempts_quantity,
+        progress.solved_puzzles_quantity,
+        format_duration(progress.elapsed),
+        progress.hash_rate,
+        estimated_remaining_time,
+    );
+
+    return ();
+}
+
+fn bench<'a>(arguments: &'a [String]) -> Result<(), Box<dyn Error + 'static>> {
+    let mut algorithm = POW::DEFAULT_ALGORITHM;
+
+    let mut duration = Duration::from_secs(5);
+
+    let mut target = Duration::from_secs(10);
+
+    let mut arguments_ = arguments.iter();
+
+    'a: loop {
+        let (argument, value) = match (arguments_.next(), arguments_.next()) {
+            (Some(argument_), Some(value_)) => (argument_.as_str(), value_.as_str()),
+            (None, _) => {
+                break 'a;
+            }
+            (Some(_), None) => {
+                return Err(Box::from(USAGE));
+            }
+        };
+
+        match (argument, value.parse::<u64>()) {
+            ("--algorithm", _) => {
+                algorithm = match value.parse::<Algorithm>() {
+                    Ok(algorithm_) => algorithm_,
+                    Err(_) => {
+                        return Err(Box::from(USAGE));
+                    }
+                };
+            }
+            ("--duration", Ok(seconds)) => {
+                duration = Duration::from_secs(seconds);
+            }
+            ("--target", Ok(seconds)) => {
+                target = Duration::from_secs(seconds);
+            }
+            _ => {
+                return Err(Box::from(USAGE));
+            }
+        }
+    }
+
+    let calibration = Calibration::measure(algorithm, duration)?;
+
+    println!(
+        "Hash rate: {:.0} {} attempts per second ({} attempts in {:.2} sec.).",
+        calibration.hash_rate,
+        calibration.algorithm,
+        calibration.attempts_quantity,
+        calibration.elapsed.as_secs_f64(),
+    );
+
+    if let Algorithm::DoubleSha256 | Algorithm::Sha256 = algorithm {
+        println!("SHA-256 backend: {}.", POW::sha256_backend());
+    }
+
+    println!(
+        "{:>10} {:>22} {:>16} {:>16} {:>16}",
+        "Zero bits", "Expected attempts", "Mean time", "95% time", "99% time",
+    );
+
+    for zero_bits in Difficulty::MINIMUM.zero_bits()..=Difficulty::MAXIMUM.zero_bits() {
+        print_estimate(&calibration.estimate(Difficulty::new(zero_bits)?));
+    }
+
+    let estimate = match calibration.recommend(target) {
+        Some(estimate_) => estimate_,
+        None => {
+            println!(
+                "No difficulty fits the {} target: the minimum one takes {} on average.",
+                format_duration(target),
+                format_duration(calibration.estimate(Difficulty::MINIMUM).mean_time),
+            );
+
+            return Ok(());
+        }
+    };
+
+    println!(
+        "Recommended difficulty for the {} target: {} zero bits.",
+        format_duration(target),
+        estimate.difficulty.zero_bits(),
+    );
+
+    print_estimate(&estimate);
+
+    return Ok(());
+}
+
+fn print_estimate<'a>(estimate: &'a Estimate) -> () {
+    println!(
+        "{:>10} {:>22.0} {:>16} {:>16} {:>16}",
+        estimate.difficulty.zero_bits(),
+        estimate.expected_attempts_quantity,
+        format_duration(estimate.mean_time),
+        format_duration(estimate.percentile_95_time),
+        format_duration(estimate.percentile_99_time),
+    );
+
+    return ();
+}
+
+fn format_duration(duration: Duration) -> String {
+    const UNITS: [(&str, f64); 5] = [
+        ("years", 365.0 * 24.0 * 60.0 * 60.0),
+        ("days", 24.0 * 60.0 * 60.0),
+        ("h.", 60.0 * 60.0),
+        ("min.", 60.0),
+        ("sec.", 1.0),
+    ];
+
+    let seconds = duration.as_secs_f64();
+
+    for (unit, unit_seconds) in UNITS.iter() {
+        if seconds >= *unit_seconds {
+            return format!("{:.2} {}", seconds / unit_seconds, unit);
+        }
+    }
+
+    return format!("{:.2} ms.", seconds * 1000.0);
+}